@@ -0,0 +1,100 @@
+use bytes::BytesMut;
+use pienirc::Message;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::TransportError;
+
+// for use with tokio_util's Framed, as an alternative to the Transport
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IrcCodec;
+
+impl Decoder for IrcCodec {
+    type Item = Message;
+    type Error = TransportError;
+
+    // the same as the Transport, so bad lines get reported and unterminated data can't pile up forever
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Message>, TransportError> {
+        crate::parse_buffered(src)
+    }
+}
+
+impl Encoder<Message> for IrcCodec {
+    type Error = TransportError;
+
+    fn encode(&mut self, item: Message, dst: &mut BytesMut) -> Result<(), TransportError> {
+        dst.extend_from_slice(&item.to_bytes()?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{SinkExt, StreamExt};
+    use tokio::io;
+    use tokio_util::codec::Framed;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn framed_round_trip() {
+        let (client, server) = io::duplex(64);
+        let mut client = Framed::new(client, IrcCodec);
+        let mut server = Framed::new(server, IrcCodec);
+
+        let messages = [
+            "PRIVMSG #chan :hello there",
+            "@id=123 :nick!user@host PRIVMSG #chan :tagged",
+            "PING :three",
+        ]
+        .map(|m| m.parse::<Message>().unwrap());
+
+        // sending one at a time, since the duplex buffer is smaller than the messages combined
+        for m in messages {
+            client.send(m.clone()).await.unwrap();
+            assert_eq!(m, server.next().await.unwrap().unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn decode_waits_for_full_line() {
+        let mut codec = IrcCodec;
+        let mut buffer = BytesMut::from(&b"PING :ab"[..]);
+
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+        assert_eq!(&b"PING :ab"[..], &buffer[..]);
+
+        buffer.extend_from_slice(b"c\r\nPI");
+        let message = codec.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(&Some("abc".to_string()), message.last_parameter());
+        assert_eq!(&b"PI"[..], &buffer[..]);
+    }
+
+    #[test]
+    fn decode_surfaces_overly_long_line() {
+        let mut codec = IrcCodec;
+        let long = format!("PRIVMSG #chan :{}\r\nPING :after\r\n", "a".repeat(900));
+        let mut buffer = BytesMut::from(long.as_bytes());
+
+        assert!(matches!(
+            codec.decode(&mut buffer),
+            Err(TransportError::Protocol(
+                pienirc::Error::ParsedMessageTooLong { .. }
+            ))
+        ));
+        let message = codec.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(&Some("after".to_string()), message.last_parameter());
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn decode_bounds_unterminated_line() {
+        let mut codec = IrcCodec;
+        let mut buffer = BytesMut::from(&[b'a'; 10 * 1024][..]);
+
+        let Err(TransportError::Io(err)) = codec.decode(&mut buffer) else {
+            panic!("Expected an I/O error")
+        };
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        assert!(buffer.is_empty());
+    }
+}
@@ -0,0 +1,20 @@
+use pienirc::registration::RegistrationError;
+use tokio::io;
+
+#[derive(thiserror::Error, Debug)]
+pub enum TransportError {
+    #[error("I/O error.")]
+    Io(#[from] io::Error),
+    #[error("Protocol error.")]
+    Protocol(#[from] pienirc::Error),
+}
+
+// so that registering over one of our transports can use `?`
+impl From<TransportError> for RegistrationError {
+    fn from(err: TransportError) -> Self {
+        match err {
+            TransportError::Io(err) => RegistrationError::Io(err),
+            TransportError::Protocol(err) => RegistrationError::Protocol(err),
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use pienirc::{KnownCommand, Message};
+use tokio::{io, time::Instant};
+
+use crate::warn;
+
+// after `idle` without hearing anything, sends a PING of its own, and declares the connection dead
+// if the matching PONG doesn't come back within `timeout`.
+// this catches half-open connections, where reads would otherwise wait forever.
+pub struct KeepAlive<T> {
+    inner: T,
+    idle: Duration,
+    timeout: Duration,
+    last_received: Instant,
+    // the token we're waiting on a PONG for, and when we give up on it
+    pending: Option<(String, Instant)>,
+    pings: u64,
+}
+
+impl<T: pienirc::Transport + Send> KeepAlive<T> {
+    pub fn new(inner: T, idle: Duration, timeout: Duration) -> Self {
+        KeepAlive {
+            inner,
+            idle,
+            timeout,
+            last_received: Instant::now(),
+            pending: None,
+            pings: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    async fn ping(&mut self) -> Result<(), T::Error> {
+        self.pings += 1;
+        let token = format!("keepalive-{}", self.pings);
+        let ping = Message::new(None, KnownCommand::Ping.into(), None::<&str>, Some(&token))?;
+
+        self.inner.send(ping).await?;
+        self.inner.flush().await?;
+        self.pending = Some((token, Instant::now() + self.timeout));
+        Ok(())
+    }
+
+    fn is_pong_for(message: &Message, token: &str) -> bool {
+        message.command().as_known() == Some(KnownCommand::Pong)
+            && message.all_params().last() == Some(token)
+    }
+}
+
+impl<T: pienirc::Transport + Send> pienirc::Transport for KeepAlive<T> {
+    type Error = T::Error;
+
+    async fn send(&mut self, message: Message) -> Result<(), T::Error> {
+        self.inner.send(message).await
+    }
+
+    // fails with io::ErrorKind::TimedOut once the connection is declared dead.
+    // the PONGs for our own PINGs are swallowed.
+    async fn receive(&mut self) -> Result<Option<Message>, T::Error> {
+        loop {
+            let deadline = match &self.pending {
+                Some((_, deadline)) => *deadline,
+                None => self.last_received + self.idle,
+            };
+
+            match tokio::time::timeout_at(deadline, self.inner.receive()).await {
+                Ok(message) => {
+                    let Some(message) = message? else {
+                        return Ok(None);
+                    };
+                    self.last_received = Instant::now();
+
+                    match &self.pending {
+                        Some((token, _)) if Self::is_pong_for(&message, token) => {
+                            self.pending = None
+                        }
+                        _ => return Ok(Some(message)),
+                    }
+                }
+                Err(_) if self.pending.is_some() => {
+                    warn!("No PONG received for keepalive PING.");
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "No PONG received for keepalive PING.",
+                    )
+                    .into());
+                }
+                Err(_) => self.ping().await?,
+            }
+        }
+    }
+
+    async fn flush(&mut self) -> Result<(), T::Error> {
+        self.inner.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pienirc::Transport as _;
+
+    use crate::{Transport, TransportError};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn silent_server_is_dead() {
+        let (client, server) = io::duplex(1024);
+        let mut client = KeepAlive::new(
+            Transport::from_stream(client),
+            Duration::from_millis(50),
+            Duration::from_millis(50),
+        );
+        let mut server = Transport::from_stream(server);
+
+        let start = Instant::now();
+        let Err(TransportError::Io(err)) = client.receive().await else {
+            panic!("Expected an I/O error")
+        };
+        assert_eq!(io::ErrorKind::TimedOut, err.kind());
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        let ping = server.receive().await.unwrap().unwrap();
+        assert_eq!(Some(KnownCommand::Ping), ping.command().as_known());
+    }
+
+    #[tokio::test]
+    async fn pong_keeps_alive() {
+        let (client, server) = io::duplex(1024);
+        let mut client = KeepAlive::new(
+            Transport::from_stream(client),
+            Duration::from_millis(50),
+            Duration::from_millis(500),
+        );
+
+        tokio::spawn(async move {
+            let mut server = Transport::from_stream(server);
+            let ping = server.receive().await.unwrap().unwrap();
+            let token = ping.trailing().unwrap().to_string();
+            for reply in [
+                format!(":irc.server PONG irc.server :{token}"),
+                "PRIVMSG #chan :still here".to_string(),
+            ] {
+                server.send(reply.parse().unwrap()).await.unwrap();
+            }
+            server.flush().await.unwrap();
+            // keeps the connection open
+            server.receive().await.unwrap();
+        });
+
+        // the PONG itself doesn't come through
+        let message = client.receive().await.unwrap().unwrap();
+        assert_eq!(Some("still here"), message.trailing());
+    }
+}
@@ -1,41 +1,745 @@
-use bytes::{Buf, BytesMut};
-use pienirc::Message;
+use std::{future::Future, time::Duration};
+
+use bytes::BytesMut;
+use pienirc::{KnownCommand, Limits, Message, Transport as _};
 use tokio::{
-    io::{self, AsyncReadExt, AsyncWriteExt, BufWriter},
-    net::TcpStream,
+    io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufWriter},
+    net::{TcpStream, ToSocketAddrs},
 };
 
-pub struct Transport {
-    stream: BufWriter<TcpStream>,
+// forwards to tracing when the feature is on, and compiles to nothing otherwise
+#[cfg(feature = "tracing")]
+use tracing::{info, trace, warn};
+#[cfg(not(feature = "tracing"))]
+macro_rules! disabled_log {
+    ($($t:tt)*) => {};
+}
+#[cfg(not(feature = "tracing"))]
+use {disabled_log as info, disabled_log as trace, disabled_log as warn};
+
+#[cfg(feature = "codec")]
+mod codec;
+mod error;
+mod keepalive;
+mod reconnect;
+mod split;
+mod throttle;
+#[cfg(feature = "rustls")]
+mod tls;
+#[cfg(feature = "websocket")]
+mod ws;
+
+#[cfg(feature = "codec")]
+pub use codec::IrcCodec;
+pub use error::TransportError;
+pub use keepalive::KeepAlive;
+pub use reconnect::{Backoff, Event, ReconnectingTransport};
+pub use split::{TransportReader, TransportWriter};
+pub use throttle::{RateLimiter, ThrottledTransport};
+#[cfg(feature = "websocket")]
+pub use ws::WsTransport;
+
+pub struct Transport<S = TcpStream> {
+    stream: BufWriter<S>,
     read_buffer: BytesMut,
+    read_bounds: ReadBufferBounds,
+    // reused between sends to avoid allocating for each message
+    write_buffer: Vec<u8>,
+    // a peer that keeps the socket open but stops responding would otherwise block us forever
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
 }
 
-impl pienirc::Transport for Transport {
-    async fn send(&mut self, message: Message) -> io::Result<()> {
-        let bytes = match message.to_bytes() {
-            Ok(bytes) => bytes,
-            Err(err) => return Err(io::Error::new(io::ErrorKind::InvalidInput, err)),
+impl Transport<TcpStream> {
+    pub fn new(stream: TcpStream) -> Self {
+        Self::from_stream(stream)
+    }
+
+    pub async fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        info!(peer = ?stream.peer_addr().ok(), "Connected.");
+        Ok(Self::new(stream))
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> Transport<S> {
+    // for anything besides plain tcp, like unix sockets or in-memory pipes for tests
+    pub fn from_stream(stream: S) -> Self {
+        Self::with_capacity(stream, 0)
+    }
+
+    // high-traffic connections may want to avoid the initial handful of buffer growths
+    pub fn with_capacity(stream: S, read_capacity: usize) -> Self {
+        Transport {
+            stream: BufWriter::new(stream),
+            read_buffer: BytesMut::with_capacity(read_capacity),
+            read_bounds: ReadBufferBounds::new(read_capacity),
+            // reused for every send, and big enough that a single message never has to grow it
+            write_buffer: Vec::with_capacity(pienirc::Limits::default().max_line),
+            read_timeout: None,
+            write_timeout: None,
+        }
+    }
+
+    // timed out sends and receives fail with io::ErrorKind::TimedOut
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) {
+        self.write_timeout = timeout;
+    }
+
+    // once the read buffer has grown past the threshold, it gets shrunk back down to the baseline
+    pub fn set_read_buffer_bounds(&mut self, baseline: usize, threshold: usize) {
+        self.read_bounds = ReadBufferBounds {
+            baseline,
+            threshold: threshold.max(baseline),
         };
+    }
 
-        self.stream.write_all(&bytes[..]).await?;
-        self.stream.flush().await?;
+    // for callers with their own event loop, where this only parses what read_more has already buffered,
+    // returning None until a complete line is there
+    pub fn try_receive(&mut self) -> Result<Option<Message>, TransportError> {
+        parse_buffered(&mut self.read_buffer)
+    }
 
-        Ok(())
+    // reads whatever is available into the buffer, where 0 means the connection was closed
+    pub async fn read_more(&mut self) -> io::Result<usize> {
+        read_more(
+            &mut self.stream,
+            &mut self.read_buffer,
+            self.read_bounds,
+            self.read_timeout,
+        )
+        .await
     }
 
-    async fn receive(&mut self) -> io::Result<Option<Message>> {
+    // answers any PINGs along the way, so that callers only see the messages they care about
+    pub async fn receive_handling_ping(&mut self) -> Result<Option<Message>, TransportError> {
         loop {
-            if let Ok(Some((message, size))) = Message::parse(&self.read_buffer) {
-                self.read_buffer.advance(size);
-                return Ok(Some(message));
-            } else if self.stream.read_buf(&mut self.read_buffer).await? == 0 {
-                if self.read_buffer.is_empty() {
-                    // data completely read
-                    return Ok(None);
-                } else {
-                    return Err(io::ErrorKind::ConnectionReset.into());
+            let Some(message) = self.receive().await? else {
+                return Ok(None);
+            };
+
+            match message.pong_reply() {
+                Some(pong) => self.send(pong).await?,
+                None => return Ok(Some(message)),
+            }
+        }
+    }
+
+    // serializes everything into one buffer for a single write, rather than one per message
+    pub async fn send_many(&mut self, messages: &[Message]) -> Result<(), TransportError> {
+        send(
+            &mut self.stream,
+            &mut self.write_buffer,
+            self.write_timeout,
+            messages,
+        )
+        .await
+    }
+
+    // for callers that want each message to go out right away, at the cost of a syscall each
+    pub async fn send_flush(&mut self, message: Message) -> Result<(), TransportError> {
+        self.send(message).await?;
+        self.flush().await
+    }
+
+    // sends the QUIT, then shuts down the write side, so that the server sees a clean disconnect
+    pub async fn quit(&mut self, reason: Option<&str>) -> Result<(), TransportError> {
+        let quit = Message::new(None, KnownCommand::Quit.into(), None::<&str>, reason)?;
+
+        self.send(quit).await?;
+        info!(reason, "Quitting.");
+        // shutting down the BufWriter flushes it first
+        Ok(self.stream.shutdown().await?)
+    }
+
+    // so that reading and writing can happen concurrently, such as from different tasks
+    pub fn split(self) -> (TransportReader<S>, TransportWriter<S>) {
+        // splitting the BufWriter itself, rather than the inner stream, means no buffered data is lost
+        let (reader, writer) = io::split(self.stream);
+        (
+            TransportReader::new(
+                reader,
+                self.read_buffer,
+                self.read_bounds,
+                self.read_timeout,
+            ),
+            TransportWriter::new(writer, self.write_buffer, self.write_timeout),
+        )
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> pienirc::Transport for Transport<S> {
+    type Error = TransportError;
+
+    async fn send(&mut self, message: Message) -> Result<(), TransportError> {
+        self.send_many(std::slice::from_ref(&message)).await
+    }
+
+    // anything still buffered gets flushed first, since the server may be waiting on it before replying
+    async fn receive(&mut self) -> Result<Option<Message>, TransportError> {
+        self.flush().await?;
+        receive(
+            &mut self.stream,
+            &mut self.read_buffer,
+            self.read_bounds,
+            self.read_timeout,
+        )
+        .await
+    }
+
+    async fn flush(&mut self) -> Result<(), TransportError> {
+        Ok(flush(&mut self.stream, self.write_timeout).await?)
+    }
+}
+
+// shared between the Transport and its split halves.
+// only writes to the BufWriter, which flushes to the stream once it fills up or gets explicitly flushed.
+async fn send<W: AsyncWrite + Unpin>(
+    stream: &mut W,
+    write_buffer: &mut Vec<u8>,
+    write_timeout: Option<Duration>,
+    messages: &[Message],
+) -> Result<(), TransportError> {
+    write_buffer.clear();
+    for message in messages {
+        #[cfg(feature = "tracing")]
+        let start = write_buffer.len();
+
+        // nothing gets written if any of the messages are bad
+        message.to_bytes_into(write_buffer)?;
+
+        // without the crlf
+        trace!(
+            direction = "out",
+            line = %truncated(&write_buffer[start..write_buffer.len() - 2])
+        );
+    }
+
+    Ok(with_timeout(write_timeout, stream.write_all(&write_buffer[..])).await?)
+}
+
+// writes the message a piece at a time, such as straight into a BufWriter, without serializing it into a buffer first
+pub async fn write_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &Message,
+) -> Result<(), TransportError> {
+    for segment in message.segments()? {
+        writer.write_all(&segment).await?;
+    }
+    Ok(())
+}
+
+async fn flush<W: AsyncWrite + Unpin>(
+    stream: &mut W,
+    write_timeout: Option<Duration>,
+) -> io::Result<()> {
+    with_timeout(write_timeout, stream.flush()).await
+}
+
+// a single large message grows the read buffer, which would otherwise stay that big for the life of the connection.
+// that adds up for something like a server with lots of mostly idle connections.
+#[derive(Debug, Clone, Copy)]
+struct ReadBufferBounds {
+    baseline: usize,
+    threshold: usize,
+}
+
+impl ReadBufferBounds {
+    // by default, only shrinks after something bigger than the largest valid line
+    fn new(baseline: usize) -> Self {
+        let limits = Limits::default();
+        let baseline = baseline.max(limits.max_line);
+        ReadBufferBounds {
+            baseline,
+            threshold: (limits.max_tags + limits.max_line).max(baseline),
+        }
+    }
+}
+
+async fn receive<R: AsyncRead + Unpin>(
+    stream: &mut R,
+    read_buffer: &mut BytesMut,
+    read_bounds: ReadBufferBounds,
+    read_timeout: Option<Duration>,
+) -> Result<Option<Message>, TransportError> {
+    loop {
+        if let Some(message) = parse_buffered(read_buffer)? {
+            return Ok(Some(message));
+        }
+
+        if read_more(stream, read_buffer, read_bounds, read_timeout).await? == 0 {
+            if read_buffer.is_empty() {
+                // data completely read
+                info!("Connection closed.");
+                return Ok(None);
+            } else {
+                return Err(io::Error::from(io::ErrorKind::ConnectionReset).into());
+            }
+        }
+    }
+}
+
+async fn read_more<R: AsyncRead + Unpin>(
+    stream: &mut R,
+    read_buffer: &mut BytesMut,
+    read_bounds: ReadBufferBounds,
+    read_timeout: Option<Duration>,
+) -> io::Result<usize> {
+    // room for at least a typical line. this also reclaims the space of what's already been consumed,
+    // so the capacity afterwards reflects everything the buffer is holding onto.
+    let line = Limits::default().max_line;
+    read_buffer.reserve(line);
+    if read_buffer.capacity() > read_bounds.threshold && read_buffer.len() <= read_bounds.baseline {
+        let mut shrunk = BytesMut::with_capacity(read_bounds.baseline + line);
+        shrunk.extend_from_slice(read_buffer);
+        *read_buffer = shrunk;
+    }
+
+    with_timeout(read_timeout, stream.read_buf(read_buffer)).await
+}
+
+// only looks at what's already been read, so never waits on the stream
+fn parse_buffered(read_buffer: &mut BytesMut) -> Result<Option<Message>, TransportError> {
+    // parsing consumes the line, so it gets peeked at beforehand
+    #[cfg(feature = "tracing")]
+    let line = tracing::enabled!(tracing::Level::TRACE)
+        .then(|| {
+            read_buffer
+                .windows(2)
+                .position(|w| w == b"\r\n")
+                .map(|p| truncated(&read_buffer[..p]))
+        })
+        .flatten();
+
+    match Message::parse_from(read_buffer) {
+        Ok(Some(message)) => {
+            trace!(direction = "in", line);
+            return Ok(Some(message));
+        }
+        // the bad line, whether malformed or overly long, has been dropped, so receiving again moves on to the next one
+        Err(err) => {
+            warn!(error = %err, "Dropped a line that failed to parse.");
+            return Err(err.into());
+        }
+        Ok(None) => (),
+    }
+
+    // a complete line can't be any longer than this, so a peer that keeps sending without a crlf is misbehaving,
+    // and we'd otherwise keep buffering forever
+    let limits = Limits::default();
+    if read_buffer.len() >= limits.max_tags + limits.max_line {
+        warn!(
+            buffered = read_buffer.len(),
+            "Dropped buffered data with no line terminator."
+        );
+        read_buffer.clear();
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Received a line longer than the limit without a crlf.",
+        )
+        .into());
+    }
+
+    Ok(None)
+}
+
+// lossy, so that non-utf-8 lines can still be logged
+#[cfg(feature = "tracing")]
+fn truncated(line: &[u8]) -> String {
+    const MAX: usize = 256;
+    match line.len() > MAX {
+        true => format!("{}...", String::from_utf8_lossy(&line[..MAX])),
+        false => String::from_utf8_lossy(line).into_owned(),
+    }
+}
+
+async fn with_timeout<T>(
+    timeout: Option<Duration>,
+    future: impl Future<Output = io::Result<T>>,
+) -> io::Result<T> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, future)
+            .await
+            .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into())),
+        None => future.await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pienirc::Command;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn connect_and_receive() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream
+                .write_all(b"PING :first\r\nPING :second\r\n")
+                .await
+                .unwrap();
+        });
+
+        let mut transport = Transport::connect(addr).await.unwrap();
+
+        for expected in ["first", "second"] {
+            let message = transport.receive().await.unwrap().unwrap();
+            assert_eq!(&Command::General("PING".to_string()), message.command());
+            assert_eq!(&Some(expected.to_string()), message.last_parameter());
+        }
+        assert!(transport.receive().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn receive_surfaces_overly_long_line() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let long = format!("PRIVMSG #chan :{}\r\n", "a".repeat(900));
+            stream.write_all(long.as_bytes()).await.unwrap();
+            stream.write_all(b"PING :after\r\n").await.unwrap();
+        });
+
+        let mut transport = Transport::connect(addr).await.unwrap();
+
+        assert!(matches!(
+            transport.receive().await,
+            Err(TransportError::Protocol(
+                pienirc::Error::ParsedMessageTooLong { .. }
+            ))
+        ));
+        let message = transport.receive().await.unwrap().unwrap();
+        assert_eq!(&Command::General("PING".to_string()), message.command());
+        assert_eq!(&Some("after".to_string()), message.last_parameter());
+    }
+
+    #[tokio::test]
+    async fn receive_bounds_unterminated_line() {
+        let (client, mut server) = io::duplex(64 * 1024);
+        let mut client = Transport::from_stream(client);
+
+        server.write_all(&[b'a'; 10 * 1024]).await.unwrap();
+        let Err(TransportError::Io(err)) = client.receive().await else {
+            panic!("Expected an I/O error")
+        };
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        assert!(client.read_buffer.is_empty());
+    }
+
+    #[tokio::test]
+    async fn receive_surfaces_malformed_line() {
+        let (client, mut server) = io::duplex(1024);
+        let mut client = Transport::from_stream(client);
+
+        server
+            .write_all(b":prefix\r\nPING :after\r\n")
+            .await
+            .unwrap();
+        assert!(matches!(
+            client.receive().await,
+            Err(TransportError::Protocol(pienirc::Error::Parsing { .. }))
+        ));
+
+        let message = client.receive().await.unwrap().unwrap();
+        assert_eq!(&Some("after".to_string()), message.last_parameter());
+    }
+
+    #[tokio::test]
+    async fn receive_handling_ping_replies() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream
+                .write_all(b"PING :abc\r\nPRIVMSG #chan :hi\r\n")
+                .await
+                .unwrap();
+
+            let mut pong = [0; 11];
+            stream.read_exact(&mut pong).await.unwrap();
+            pong
+        });
+
+        let mut transport = Transport::connect(addr).await.unwrap();
+
+        let message = transport.receive_handling_ping().await.unwrap().unwrap();
+        assert_eq!(&Command::General("PRIVMSG".to_string()), message.command());
+        assert_eq!(b"PONG :abc\r\n", &server.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn split_concurrently() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // echo server
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let (mut reader, mut writer) = stream.split();
+            io::copy(&mut reader, &mut writer).await.unwrap();
+        });
+
+        let (mut reader, mut writer) = Transport::connect(addr).await.unwrap().split();
+
+        let reading = tokio::spawn(async move {
+            let mut received = Vec::new();
+            for _ in 0..3 {
+                received.push(reader.receive().await.unwrap().unwrap());
+            }
+            received
+        });
+
+        let messages = ["PRIVMSG #chan :one", "PRIVMSG #chan :two", "PING :three"]
+            .map(|m| m.parse::<Message>().unwrap());
+        let writing = tokio::spawn({
+            let messages = messages.clone();
+            async move {
+                for m in messages {
+                    writer.send(m).await.unwrap();
                 }
+                writer.flush().await.unwrap();
+            }
+        });
+
+        writing.await.unwrap();
+        assert_eq!(messages.to_vec(), reading.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn from_stream_over_duplex() {
+        let (client, server) = io::duplex(1024);
+        let mut client = Transport::from_stream(client);
+        let mut server = Transport::from_stream(server);
+
+        let message: Message = ":nick!user@host PRIVMSG #chan :hello".parse().unwrap();
+        client.send_flush(message.clone()).await.unwrap();
+        assert_eq!(Some(message), server.receive().await.unwrap());
+
+        drop(client);
+        assert!(server.receive().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn try_receive_only_parses_buffered() {
+        let (client, mut server) = io::duplex(1024);
+        let mut client = Transport::from_stream(client);
+        assert!(client.try_receive().unwrap().is_none());
+
+        server.write_all(b"PING :par").await.unwrap();
+        assert_eq!(9, client.read_more().await.unwrap());
+        assert!(client.try_receive().unwrap().is_none());
+
+        server.write_all(b"tial\r\nPING :next\r\n").await.unwrap();
+        client.read_more().await.unwrap();
+        assert_eq!(
+            Some("PING :partial".parse().unwrap()),
+            client.try_receive().unwrap()
+        );
+        assert_eq!(
+            Some("PING :next".parse().unwrap()),
+            client.try_receive().unwrap()
+        );
+        assert!(client.try_receive().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn write_message_matches_to_bytes() {
+        let message: Message = "@a=b\\s :nick!user@host PRIVMSG #chan :hello there"
+            .parse()
+            .unwrap();
+
+        let mut written = Vec::new();
+        write_message(&mut written, &message).await.unwrap();
+        assert_eq!(message.to_bytes().unwrap(), written);
+    }
+
+    #[tokio::test]
+    async fn send_many_concatenates() {
+        let (client, mut server) = io::duplex(1024);
+        let mut client = Transport::from_stream(client);
+
+        let messages =
+            ["NICK bot", "USER bot 0 * :Bot", "JOIN #chan"].map(|m| m.parse::<Message>().unwrap());
+        client.send_many(&messages).await.unwrap();
+        client.flush().await.unwrap();
+        drop(client);
+
+        let mut received = Vec::new();
+        server.read_to_end(&mut received).await.unwrap();
+        assert_eq!(
+            &b"NICK bot\r\nUSER bot 0 * :Bot\r\nJOIN #chan\r\n"[..],
+            &received[..]
+        );
+    }
+
+    #[tokio::test]
+    async fn send_buffers_until_flush() {
+        let (client, mut server) = io::duplex(1024);
+        let mut client = Transport::from_stream(client);
+
+        client.send("PING :one".parse().unwrap()).await.unwrap();
+        client.send("PING :two".parse().unwrap()).await.unwrap();
+
+        let mut received = [0; 22];
+        let early = tokio::time::timeout(Duration::from_millis(50), server.read(&mut received));
+        assert!(
+            early.await.is_err(),
+            "nothing should arrive before flushing"
+        );
+
+        client.flush().await.unwrap();
+        server.read_exact(&mut received).await.unwrap();
+        assert_eq!(b"PING :one\r\nPING :two\r\n", &received);
+    }
+
+    #[tokio::test]
+    async fn quit_shuts_down() {
+        let (client, mut server) = io::duplex(1024);
+        let mut client = Transport::from_stream(client);
+
+        client.quit(Some("going away")).await.unwrap();
+
+        // reading to the end only finishes because the write half got shut down
+        let mut received = Vec::new();
+        server.read_to_end(&mut received).await.unwrap();
+        assert_eq!(b"QUIT :going away\r\n", &received[..]);
+    }
+
+    #[tokio::test]
+    async fn receive_times_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // keeps the connection open without ever sending anything
+        let server = tokio::spawn(async move { listener.accept().await.unwrap() });
+
+        let mut transport = Transport::connect(addr).await.unwrap();
+        transport.set_read_timeout(Some(Duration::from_millis(50)));
+
+        let Err(TransportError::Io(err)) = transport.receive().await else {
+            panic!("Expected an I/O error")
+        };
+        assert_eq!(io::ErrorKind::TimedOut, err.kind());
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn with_capacity_presizes_read_buffer() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+
+        let transport = Transport::with_capacity(stream, 4096);
+        assert!(transport.read_buffer.capacity() >= 4096);
+    }
+
+    #[tokio::test]
+    async fn read_buffer_shrinks_after_large_message() {
+        let (client, mut server) = io::duplex(64 * 1024);
+        let mut client = Transport::from_stream(client);
+        client.set_read_buffer_bounds(512, 1024);
+
+        let large = format!("@a={} PRIVMSG #chan :hi\r\n", "b".repeat(3000));
+        server.write_all(large.as_bytes()).await.unwrap();
+        client.receive().await.unwrap().unwrap();
+
+        for i in 0..100 {
+            server
+                .write_all(format!("PING :{i}\r\n").as_bytes())
+                .await
+                .unwrap();
+            client.receive().await.unwrap().unwrap();
+        }
+        assert!(
+            client.read_buffer.capacity() <= 1024,
+            "{}",
+            client.read_buffer.capacity()
+        );
+    }
+
+    #[tokio::test]
+    async fn send_reuses_write_buffer() {
+        let (client, mut server) = io::duplex(64 * 1024);
+        let mut client = Transport::from_stream(client);
+        tokio::spawn(async move { io::copy(&mut server, &mut io::sink()).await });
+
+        let message: Message = "PRIVMSG #chan :hello".parse().unwrap();
+        let capacity = client.write_buffer.capacity();
+        for _ in 0..100 {
+            client.send(message.clone()).await.unwrap();
+            assert_eq!(capacity, client.write_buffer.capacity());
+        }
+        client.flush().await.unwrap();
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn traces_lines() {
+        use std::sync::{Arc, Mutex};
+
+        // collects everything the subscriber writes
+        #[derive(Clone, Default)]
+        struct Captured(Arc<Mutex<Vec<u8>>>);
+        impl std::io::Write for Captured {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
             }
         }
+
+        let captured = Captured::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::TRACE)
+            .with_ansi(false)
+            .with_writer({
+                let captured = captured.clone();
+                move || captured.clone()
+            })
+            .finish();
+        // the test runtime is single threaded, so this covers everything below
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let (client, mut server) = io::duplex(1024);
+        let mut client = Transport::from_stream(client);
+
+        client
+            .send_flush("PRIVMSG #chan :hello".parse().unwrap())
+            .await
+            .unwrap();
+        server
+            .write_all(b":prefix-only\r\nPRIVMSG #chan :caf\xe9\r\n")
+            .await
+            .unwrap();
+        drop(server);
+        assert!(matches!(
+            client.receive().await,
+            Err(TransportError::Protocol(_))
+        ));
+        client.receive().await.unwrap().unwrap();
+        assert!(client.receive().await.unwrap().is_none());
+
+        let logs = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains(r#"direction="out" line=PRIVMSG #chan :hello"#));
+        assert!(logs.contains("WARN"));
+        assert!(logs.contains("Dropped a line that failed to parse."));
+        assert!(logs.contains("direction=\"in\" line=\"PRIVMSG #chan :caf\u{fffd}\""));
+        assert!(logs.contains("Connection closed."));
     }
 }
@@ -0,0 +1,214 @@
+use std::{collections::hash_map::RandomState, hash::BuildHasher, time::Duration};
+
+use pienirc::{Message, Transport as _};
+use tokio::net::{TcpStream, ToSocketAddrs};
+
+use crate::{info, Transport, TransportError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    pub base: Duration,
+    pub max: Duration,
+    // up to this much is randomly added to each delay, so that many clients don't reconnect in lockstep
+    pub jitter: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            base: Duration::from_secs(1),
+            max: Duration::from_secs(120),
+            jitter: Duration::from_secs(1),
+        }
+    }
+}
+
+impl Backoff {
+    fn delay(&self, attempt: u32) -> Duration {
+        let delay = self
+            .base
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max);
+
+        // RandomState is randomly seeded, which is good enough for jitter without pulling in an rng crate
+        let jitter = match self.jitter.as_nanos() {
+            0 => 0,
+            n => RandomState::new().hash_one(attempt) as u128 % (n + 1),
+        };
+
+        delay.saturating_add(Duration::from_nanos(jitter as u64))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Message(Message),
+    // registration has been replayed, but anything else, like joined channels, needs redoing by the caller
+    Reconnected,
+}
+
+pub struct ReconnectingTransport<A, F> {
+    addr: A,
+    backoff: Backoff,
+    register: F,
+    transport: Transport<TcpStream>,
+    // set when a send reconnects, so that the next receive can report it
+    reconnected: bool,
+}
+
+impl<A, F> ReconnectingTransport<A, F>
+where
+    A: ToSocketAddrs + Clone,
+    F: Fn() -> Vec<Message>,
+{
+    // the initial connection isn't retried, since failing here more likely means bad parameters
+    pub async fn connect(addr: A, backoff: Backoff, register: F) -> Result<Self, TransportError> {
+        let mut transport = Transport::connect(addr.clone()).await?;
+        Self::register(&mut transport, &register).await?;
+
+        Ok(ReconnectingTransport {
+            addr,
+            backoff,
+            register,
+            transport,
+            reconnected: false,
+        })
+    }
+
+    pub async fn receive(&mut self) -> Event {
+        loop {
+            if std::mem::take(&mut self.reconnected) {
+                return Event::Reconnected;
+            }
+
+            match self.transport.receive().await {
+                Ok(Some(message)) => return Event::Message(message),
+                // only the one line was bad, so there's no need to give up on the connection
+                Err(TransportError::Protocol(_)) => continue,
+                // the server closing the connection is as much a reason to reconnect as an error
+                Ok(None) | Err(_) => self.reconnect().await,
+            }
+        }
+    }
+
+    pub async fn send(&mut self, message: Message) -> Result<(), TransportError> {
+        match self.transport.send(message.clone()).await {
+            // the message itself is bad, so reconnecting won't help
+            Err(err @ TransportError::Protocol(_)) => Err(err),
+            Err(_) => {
+                self.reconnect().await;
+                self.transport.send(message).await
+            }
+            Ok(()) => Ok(()),
+        }
+    }
+
+    pub async fn flush(&mut self) -> Result<(), TransportError> {
+        self.transport.flush().await
+    }
+
+    async fn reconnect(&mut self) {
+        let mut attempt = 0;
+        loop {
+            tokio::time::sleep(self.backoff.delay(attempt)).await;
+            attempt = attempt.saturating_add(1);
+            info!(attempt, "Reconnecting.");
+
+            let Ok(mut transport) = Transport::connect(self.addr.clone()).await else {
+                continue;
+            };
+            if Self::register(&mut transport, &self.register).await.is_ok() {
+                self.transport = transport;
+                self.reconnected = true;
+                return;
+            }
+        }
+    }
+
+    async fn register(
+        transport: &mut Transport<TcpStream>,
+        register: &F,
+    ) -> Result<(), TransportError> {
+        for message in register() {
+            transport.send(message).await?;
+        }
+        transport.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    #[test]
+    fn backoff_delay() {
+        let backoff = Backoff {
+            base: Duration::from_millis(100),
+            max: Duration::from_millis(500),
+            jitter: Duration::ZERO,
+        };
+
+        assert_eq!(Duration::from_millis(100), backoff.delay(0));
+        assert_eq!(Duration::from_millis(400), backoff.delay(2));
+        assert_eq!(Duration::from_millis(500), backoff.delay(3));
+        assert_eq!(Duration::from_millis(500), backoff.delay(u32::MAX));
+
+        let backoff = Backoff {
+            jitter: Duration::from_millis(50),
+            ..backoff
+        };
+        for attempt in 0..10 {
+            let delay = backoff.delay(attempt);
+            let base = backoff.base * 2u32.pow(attempt);
+            assert!(delay >= base.min(backoff.max));
+            assert!(delay <= base.min(backoff.max) + backoff.jitter);
+        }
+    }
+
+    #[tokio::test]
+    async fn reconnects_and_reregisters() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let mut registrations = Vec::new();
+            for i in 0..2 {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut stream = BufReader::new(stream);
+
+                let mut line = String::new();
+                stream.read_line(&mut line).await.unwrap();
+                registrations.push(line);
+
+                // the first connection just gets dropped
+                if i == 1 {
+                    stream.write_all(b"PING :hi\r\n").await.unwrap();
+                }
+            }
+            registrations
+        });
+
+        let backoff = Backoff {
+            base: Duration::from_millis(10),
+            max: Duration::from_millis(100),
+            jitter: Duration::ZERO,
+        };
+        let mut transport = ReconnectingTransport::connect(addr, backoff, || {
+            vec!["NICK bot".parse::<Message>().unwrap()]
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(Event::Reconnected, transport.receive().await);
+        assert_eq!(
+            Event::Message("PING :hi".parse().unwrap()),
+            transport.receive().await
+        );
+        assert_eq!(vec!["NICK bot\r\n"; 2], server.await.unwrap());
+    }
+}
@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use bytes::BytesMut;
+use pienirc::Message;
+
+use crate::{ReadBufferBounds, TransportError};
+use tokio::{
+    io::{self, AsyncRead, AsyncWrite, BufWriter, ReadHalf, WriteHalf},
+    net::TcpStream,
+};
+
+pub struct TransportReader<S = TcpStream> {
+    stream: ReadHalf<BufWriter<S>>,
+    read_buffer: BytesMut,
+    read_bounds: ReadBufferBounds,
+    read_timeout: Option<Duration>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> TransportReader<S> {
+    pub(crate) fn new(
+        stream: ReadHalf<BufWriter<S>>,
+        read_buffer: BytesMut,
+        read_bounds: ReadBufferBounds,
+        read_timeout: Option<Duration>,
+    ) -> Self {
+        TransportReader {
+            stream,
+            read_buffer,
+            read_bounds,
+            read_timeout,
+        }
+    }
+
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    pub async fn receive(&mut self) -> Result<Option<Message>, TransportError> {
+        crate::receive(
+            &mut self.stream,
+            &mut self.read_buffer,
+            self.read_bounds,
+            self.read_timeout,
+        )
+        .await
+    }
+
+    pub fn try_receive(&mut self) -> Result<Option<Message>, TransportError> {
+        crate::parse_buffered(&mut self.read_buffer)
+    }
+
+    pub async fn read_more(&mut self) -> io::Result<usize> {
+        crate::read_more(
+            &mut self.stream,
+            &mut self.read_buffer,
+            self.read_bounds,
+            self.read_timeout,
+        )
+        .await
+    }
+}
+
+pub struct TransportWriter<S = TcpStream> {
+    stream: WriteHalf<BufWriter<S>>,
+    write_buffer: Vec<u8>,
+    write_timeout: Option<Duration>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> TransportWriter<S> {
+    pub(crate) fn new(
+        stream: WriteHalf<BufWriter<S>>,
+        write_buffer: Vec<u8>,
+        write_timeout: Option<Duration>,
+    ) -> Self {
+        TransportWriter {
+            stream,
+            write_buffer,
+            write_timeout,
+        }
+    }
+
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) {
+        self.write_timeout = timeout;
+    }
+
+    pub async fn send(&mut self, message: Message) -> Result<(), TransportError> {
+        self.send_many(std::slice::from_ref(&message)).await
+    }
+
+    pub async fn send_flush(&mut self, message: Message) -> Result<(), TransportError> {
+        self.send(message).await?;
+        self.flush().await
+    }
+
+    pub async fn send_many(&mut self, messages: &[Message]) -> Result<(), TransportError> {
+        crate::send(
+            &mut self.stream,
+            &mut self.write_buffer,
+            self.write_timeout,
+            messages,
+        )
+        .await
+    }
+
+    // the reader half can't flush on our behalf, so this needs calling once everything is queued
+    pub async fn flush(&mut self) -> Result<(), TransportError> {
+        Ok(crate::flush(&mut self.stream, self.write_timeout).await?)
+    }
+}
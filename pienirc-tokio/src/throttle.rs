@@ -0,0 +1,142 @@
+use std::time::Duration;
+
+use pienirc::Message;
+use tokio::time::Instant;
+
+// a token bucket, where each message takes a token and tokens come back one per refill interval
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    burst: u32,
+    refill: Duration,
+    tokens: u32,
+    last_refill: Instant,
+}
+
+impl Default for RateLimiter {
+    // roughly what servers tolerate before flood kicking
+    fn default() -> Self {
+        Self::new(5, Duration::from_secs(2))
+    }
+}
+
+impl RateLimiter {
+    pub fn new(burst: u32, refill: Duration) -> Self {
+        RateLimiter {
+            burst,
+            refill,
+            tokens: burst,
+            last_refill: Instant::now(),
+        }
+    }
+
+    // waits until a token is available, then takes it
+    pub async fn acquire(&mut self) {
+        self.refill();
+        if self.tokens == 0 {
+            tokio::time::sleep_until(self.last_refill + self.refill).await;
+            self.refill();
+        }
+        self.tokens = self.tokens.saturating_sub(1);
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        if self.refill.is_zero() || self.tokens >= self.burst {
+            // a full bucket doesn't bank time toward future tokens
+            self.tokens = self.burst;
+            self.last_refill = now;
+            return;
+        }
+
+        let refilled = (now.duration_since(self.last_refill).as_nanos() / self.refill.as_nanos())
+            .min(u32::MAX as u128) as u32;
+        if refilled > 0 {
+            self.tokens = self.tokens.saturating_add(refilled).min(self.burst);
+            self.last_refill = match self.tokens {
+                t if t == self.burst => now,
+                _ => self.last_refill + self.refill * refilled,
+            };
+        }
+    }
+}
+
+// sends beyond the limiter's allowance wait until a token is available
+pub struct ThrottledTransport<T> {
+    inner: T,
+    limiter: RateLimiter,
+}
+
+impl<T: pienirc::Transport + Send> ThrottledTransport<T> {
+    pub fn new(inner: T, limiter: RateLimiter) -> Self {
+        ThrottledTransport { inner, limiter }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: pienirc::Transport + Send> pienirc::Transport for ThrottledTransport<T> {
+    type Error = T::Error;
+
+    // flushes each message, since the point is for it to go out at the throttled time
+    async fn send(&mut self, message: Message) -> Result<(), T::Error> {
+        self.limiter.acquire().await;
+        self.inner.send(message).await?;
+        self.inner.flush().await
+    }
+
+    async fn receive(&mut self) -> Result<Option<Message>, T::Error> {
+        self.inner.receive().await
+    }
+
+    async fn flush(&mut self) -> Result<(), T::Error> {
+        self.inner.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pienirc::Transport as _;
+    use tokio::io;
+
+    use crate::Transport;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn throttles_beyond_burst() {
+        let (client, server) = io::duplex(4096);
+        let mut client = ThrottledTransport::new(
+            Transport::from_stream(client),
+            RateLimiter::new(2, Duration::from_millis(50)),
+        );
+        let mut server = Transport::from_stream(server);
+
+        let start = Instant::now();
+        for i in 0..5 {
+            client
+                .send(format!("PRIVMSG #chan :{}", i).parse().unwrap())
+                .await
+                .unwrap();
+        }
+        // the first two go out immediately, and the other three wait for a token each
+        assert!(start.elapsed() >= Duration::from_millis(150));
+
+        for i in 0..5 {
+            let message = server.receive().await.unwrap().unwrap();
+            assert_eq!(&Some(i.to_string()), message.last_parameter());
+        }
+    }
+
+    #[tokio::test]
+    async fn burst_is_immediate() {
+        let mut limiter = RateLimiter::new(3, Duration::from_secs(60));
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
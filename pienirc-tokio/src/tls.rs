@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use tokio::{
+    io,
+    net::{TcpStream, ToSocketAddrs},
+};
+use tokio_rustls::{
+    client::TlsStream,
+    rustls::{pki_types::ServerName, ClientConfig, RootCertStore},
+    TlsConnector,
+};
+
+use crate::Transport;
+
+impl Transport<TlsStream<TcpStream>> {
+    pub async fn connect_tls(addr: impl ToSocketAddrs, domain: &str) -> io::Result<Self> {
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        let config = ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+        Self::connect_tls_with_config(addr, domain, Arc::new(config)).await
+    }
+
+    // mainly for networks with private CAs (or tests), where the webpki roots won't do
+    pub async fn connect_tls_with_config(
+        addr: impl ToSocketAddrs,
+        domain: &str,
+        config: Arc<ClientConfig>,
+    ) -> io::Result<Self> {
+        let domain = ServerName::try_from(domain.to_string())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let stream = TcpStream::connect(addr).await?;
+        let stream = TlsConnector::from(config).connect(domain, stream).await?;
+
+        Ok(Transport::from_stream(stream))
+    }
+}
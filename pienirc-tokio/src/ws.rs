@@ -0,0 +1,109 @@
+use bytes::BytesMut;
+use futures_util::{SinkExt, StreamExt};
+use pienirc::Message;
+use tokio::{
+    io::{self, AsyncRead, AsyncWrite},
+    net::TcpStream,
+};
+use tokio_tungstenite::{
+    tungstenite::{self, Message as Frame},
+    MaybeTlsStream, WebSocketStream,
+};
+
+use crate::TransportError;
+
+// per the ircv3 websocket spec, each frame is a single line without the crlf.
+// some gateways instead treat frames as a stream of regular crlf-terminated lines, so those work too,
+// including lines split across frames.
+pub struct WsTransport<S = MaybeTlsStream<TcpStream>> {
+    stream: WebSocketStream<S>,
+    read_buffer: BytesMut,
+}
+
+impl WsTransport {
+    // like `wss://irc.example.com/webirc`
+    pub async fn connect(url: &str) -> io::Result<Self> {
+        let (stream, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(ws_error)?;
+        Ok(Self::from_stream(stream))
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> WsTransport<S> {
+    // for when the handshake needs customizing, such as to ask for the `text.ircv3.net` subprotocol
+    pub fn from_stream(stream: WebSocketStream<S>) -> Self {
+        WsTransport {
+            stream,
+            read_buffer: BytesMut::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> WebSocketStream<S> {
+        self.stream
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> pienirc::Transport for WsTransport<S> {
+    type Error = TransportError;
+
+    async fn send(&mut self, message: Message) -> Result<(), TransportError> {
+        let mut line = message.to_bytes()?;
+        line.truncate(line.len() - 2);
+        let line =
+            String::from_utf8(line).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        Ok(self
+            .stream
+            .feed(Frame::Text(line))
+            .await
+            .map_err(ws_error)?)
+    }
+
+    // anything still buffered gets flushed first, since the server may be waiting on it before replying
+    async fn receive(&mut self) -> Result<Option<Message>, TransportError> {
+        self.flush().await?;
+
+        loop {
+            // same as the regular transport, including bounding stream-style frames that never end the line
+            if let Some(message) = crate::parse_buffered(&mut self.read_buffer)? {
+                return Ok(Some(message));
+            }
+
+            let data = match self.stream.next().await {
+                Some(Ok(Frame::Text(text))) => text.into_bytes(),
+                Some(Ok(Frame::Binary(data))) => data,
+                Some(Ok(Frame::Close(_))) | None => {
+                    return match self.read_buffer.is_empty() {
+                        true => Ok(None),
+                        false => Err(io::Error::from(io::ErrorKind::ConnectionReset).into()),
+                    };
+                }
+                // pings get answered by tungstenite itself
+                Some(Ok(_)) => continue,
+                Some(Err(err)) => return Err(ws_error(err).into()),
+            };
+
+            // a frame without a line terminator is a whole line, unless it continues one from a previous frame
+            let whole_line = self.read_buffer.is_empty() && !data.contains(&b'\n');
+            self.read_buffer.extend_from_slice(&data);
+            if whole_line {
+                self.read_buffer.extend_from_slice(b"\r\n");
+            }
+        }
+    }
+
+    async fn flush(&mut self) -> Result<(), TransportError> {
+        Ok(self.stream.flush().await.map_err(ws_error)?)
+    }
+}
+
+fn ws_error(err: tungstenite::Error) -> io::Error {
+    match err {
+        tungstenite::Error::Io(err) => err,
+        tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed => {
+            io::ErrorKind::ConnectionReset.into()
+        }
+        err => io::Error::new(io::ErrorKind::Other, err),
+    }
+}
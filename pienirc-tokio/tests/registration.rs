@@ -0,0 +1,136 @@
+use pienirc::{
+    nick::NickFallback,
+    registration::{register, RegistrationConfig, RegistrationError},
+    Message, Transport as _,
+};
+use pienirc_tokio::Transport;
+use tokio::io::{self, AsyncWriteExt, DuplexStream};
+
+// replies to each line the client sends with whatever the script says, until the script runs out
+async fn scripted_server(
+    stream: DuplexStream,
+    script: Vec<(&'static str, Vec<&'static str>)>,
+) -> Vec<String> {
+    let mut server = Transport::from_stream(stream);
+    let mut received = Vec::new();
+    for (expected, replies) in script {
+        let message = server.receive().await.unwrap().unwrap();
+        let line = message.to_string();
+        assert_eq!(expected, line, "unexpected line from the client");
+        received.push(line);
+
+        for reply in replies {
+            server
+                .send(reply.parse::<Message>().unwrap())
+                .await
+                .unwrap();
+        }
+        server.flush().await.unwrap();
+    }
+    received
+}
+
+#[tokio::test]
+async fn registers_with_caps_and_retries() {
+    let (client, server) = io::duplex(4096);
+    let server = tokio::spawn(scripted_server(
+        server,
+        vec![
+            ("CAP LS 302", vec![]),
+            ("PASS :secret", vec![]),
+            ("NICK bot", vec![]),
+            (
+                "USER bot 0 * :The Bot",
+                vec![":irc.server CAP * LS :multi-prefix sasl", "PING :during"],
+            ),
+            (
+                "CAP REQ :multi-prefix",
+                vec![":irc.server CAP * ACK :multi-prefix"],
+            ),
+            ("PONG :during", vec![]),
+            (
+                "CAP END",
+                vec![":irc.server 433 * bot :Nickname is already in use"],
+            ),
+            ("NICK bot_", vec![":irc.server 001 bot_ :Welcome to IRC"]),
+        ],
+    ));
+
+    let mut transport = Transport::from_stream(client);
+    let mut config = RegistrationConfig::new("bot", "bot", "The Bot");
+    config.password = Some("secret".to_string());
+    config.capabilities = vec!["multi-prefix".to_string(), "away-notify".to_string()];
+
+    let outcome = register(&mut transport, config).await.unwrap();
+    assert_eq!("bot_", outcome.nick);
+    assert!(outcome.capabilities.contains("multi-prefix"));
+    assert_eq!(Some("Welcome to IRC"), outcome.welcome.trailing());
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn fails_when_nicks_run_out() {
+    let (client, server) = io::duplex(4096);
+    let server = tokio::spawn(scripted_server(
+        server,
+        vec![
+            ("NICK bot", vec![]),
+            (
+                "USER bot 0 * :Bot",
+                vec![":irc.server 433 * bot :Nickname is already in use"],
+            ),
+            (
+                "NICK alt",
+                vec![":irc.server 433 * alt :Nickname is already in use"],
+            ),
+        ],
+    ));
+
+    let mut transport = Transport::from_stream(client);
+    let mut config = RegistrationConfig::new("bot", "bot", "Bot");
+    config.fallback = NickFallback::candidates(["alt"]);
+
+    let err = register(&mut transport, config).await.unwrap_err();
+    assert!(matches!(err, RegistrationError::NicksExhausted));
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn fails_on_bad_password() {
+    let (client, server) = io::duplex(4096);
+    let server = tokio::spawn(scripted_server(
+        server,
+        vec![
+            ("PASS :wrong", vec![]),
+            ("NICK bot", vec![]),
+            (
+                "USER bot 0 * :Bot",
+                vec![":irc.server 464 * :Password incorrect"],
+            ),
+        ],
+    ));
+
+    let mut transport = Transport::from_stream(client);
+    let mut config = RegistrationConfig::new("bot", "bot", "Bot");
+    config.password = Some("wrong".to_string());
+
+    let err = register(&mut transport, config).await.unwrap_err();
+    assert!(matches!(err, RegistrationError::PasswordMismatch));
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn skips_garbled_lines() {
+    let (client, mut server) = io::duplex(4096);
+    server
+        .write_all(b":prefix\r\n:irc.server 001 bot :Welcome to IRC\r\n")
+        .await
+        .unwrap();
+
+    let mut transport = Transport::from_stream(client);
+    let outcome = register(&mut transport, RegistrationConfig::new("bot", "bot", "Bot"))
+        .await
+        .unwrap();
+    assert_eq!("bot", outcome.nick);
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "rustls")]
+
+use std::sync::Arc;
+
+use pienirc::{Command, Message, Transport as _};
+use pienirc_tokio::Transport;
+use tokio::net::TcpListener;
+use tokio_rustls::{
+    rustls::{
+        pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer},
+        ClientConfig, RootCertStore, ServerConfig,
+    },
+    TlsAcceptor,
+};
+
+#[tokio::test]
+async fn tls_round_trip() {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let cert_der = cert.cert.der().clone();
+    let key_der = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der()));
+
+    let server_config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert_der.clone()], key_der)
+        .unwrap();
+    let acceptor = TlsAcceptor::from(Arc::new(server_config));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // echo server
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let stream = acceptor.accept(stream).await.unwrap();
+        let (mut reader, mut writer) = tokio::io::split(stream);
+        tokio::io::copy(&mut reader, &mut writer).await.unwrap();
+    });
+
+    let mut roots = RootCertStore::empty();
+    roots.add(cert_der).unwrap();
+    let client_config = ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    let mut transport =
+        Transport::connect_tls_with_config(addr, "localhost", Arc::new(client_config))
+            .await
+            .unwrap();
+
+    let message = Message::new(
+        None,
+        Command::General("PRIVMSG".to_string()),
+        ["#chan"],
+        Some("hello over tls"),
+    )
+    .unwrap();
+
+    transport.send(message.clone()).await.unwrap();
+    let received = transport.receive().await.unwrap().unwrap();
+
+    assert_eq!(message, received);
+}
@@ -0,0 +1,110 @@
+#![cfg(feature = "websocket")]
+
+use futures_util::{SinkExt, StreamExt};
+use pienirc::{Command, Message, Transport as _};
+use pienirc_tokio::{TransportError, WsTransport};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message as Frame;
+
+#[tokio::test]
+async fn websocket_round_trip() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // echo server
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+        while let Some(Ok(frame)) = ws.next().await {
+            if frame.is_text() || frame.is_binary() {
+                ws.send(frame).await.unwrap();
+            }
+        }
+    });
+
+    let mut transport = WsTransport::connect(&format!("ws://{addr}/webirc"))
+        .await
+        .unwrap();
+
+    let message = Message::new(
+        None,
+        Command::General("PRIVMSG".to_string()),
+        ["#chan"],
+        Some("hello over websocket"),
+    )
+    .unwrap();
+
+    transport.send(message.clone()).await.unwrap();
+    let received = transport.receive().await.unwrap().unwrap();
+    assert_eq!(message, received);
+}
+
+#[tokio::test]
+async fn websocket_stream_style_frames() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+        // several lines in one frame, then a line split across frames, then a spec-style frame
+        for frame in [
+            "PING :a\r\nPING :b\r\nPRIV",
+            "MSG #chan :split\r\n",
+            "NOTICE me :one line",
+        ] {
+            ws.send(Frame::Text(frame.to_string())).await.unwrap();
+        }
+        ws.close(None).await.unwrap();
+    });
+
+    let mut transport = WsTransport::connect(&format!("ws://{addr}/webirc"))
+        .await
+        .unwrap();
+
+    let mut received = Vec::new();
+    while let Some(message) = transport.receive().await.unwrap() {
+        received.push(message.to_string());
+    }
+    assert_eq!(
+        vec![
+            "PING :a",
+            "PING :b",
+            "PRIVMSG #chan :split",
+            "NOTICE me :one line"
+        ],
+        received
+    );
+}
+
+#[tokio::test]
+async fn websocket_bounds_unterminated_line() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+        // the first frame starts a line that the rest keep continuing without ever ending
+        ws.send(Frame::Text("PING :a\r\nPRIVMSG #chan :".to_string()))
+            .await
+            .unwrap();
+        for _ in 0..10 {
+            ws.send(Frame::Text("a".repeat(1024))).await.unwrap();
+        }
+        // held open, so that the only way out is the bound
+        while ws.next().await.is_some() {}
+    });
+
+    let mut transport = WsTransport::connect(&format!("ws://{addr}/webirc"))
+        .await
+        .unwrap();
+
+    let message = transport.receive().await.unwrap().unwrap();
+    assert_eq!("PING :a", message.to_string());
+
+    let Err(TransportError::Io(err)) = transport.receive().await else {
+        panic!("Expected an I/O error")
+    };
+    assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+}
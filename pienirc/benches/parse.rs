@@ -0,0 +1,35 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use pienirc::Message;
+
+const LINES: &[&[u8]] = &[
+    b"PING :irc.example.net\r\n",
+    b":nick!user@host.example.com PRIVMSG #channel :hello there, how is everyone doing today?\r\n",
+    b":irc.example.net 353 me = #channel :@op +voiced regular another yetanother\r\n",
+    b"@time=2023-01-01T00:00:00.000Z;msgid=abc123 :nick!user@host JOIN #channel\r\n",
+    b":irc.example.net 005 me CHANTYPES=# PREFIX=(ov)@+ NETWORK=Example CASEMAPPING=rfc1459 :are supported by this server\r\n",
+];
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+
+    group.bench_function("hand-written", |b| {
+        b.iter(|| {
+            for line in LINES {
+                black_box(Message::parse(black_box(line)).unwrap());
+            }
+        })
+    });
+
+    group.bench_function("regex", |b| {
+        b.iter(|| {
+            for line in LINES {
+                black_box(Message::parse_regex(black_box(line)).unwrap());
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -0,0 +1,174 @@
+// proptest strategies that only generate messages that survive a trip through to_bytes and parse unchanged
+use proptest::{
+    arbitrary::{any, Arbitrary},
+    collection::vec,
+    option, prop_oneof,
+    strategy::{BoxedStrategy, Strategy},
+};
+
+use crate::{Command, KnownCommand, Message, Prefix, Tag, UserMask};
+
+impl Arbitrary for Tag {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    // values get escaped, so anything goes.
+    // an empty value is written as `key=`, which reads back as an empty value rather than as no value.
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        ("\\+?[a-zA-Z0-9/.-]{1,12}", option::of("(?s).{0,20}"))
+            .prop_map(|(key, value)| Tag { key, value })
+            .boxed()
+    }
+}
+
+impl Arbitrary for UserMask {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            "[a-zA-Z\\[\\]\\\\`_^{|}][a-zA-Z0-9\\[\\]\\\\`_^{|}-]{0,8}",
+            option::of("~?[a-z0-9]{1,9}"),
+            option::of("[a-z0-9-]{1,10}(\\.[a-z0-9-]{1,10}){0,3}"),
+        )
+            .prop_map(|(nickname, user, server)| UserMask {
+                nickname,
+                user,
+                server,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Prefix {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    // server names need a dot, or else they'd read back as a nick
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            "[a-z0-9-]{1,10}(\\.[a-z0-9-]{1,10}){1,3}".prop_map(Prefix::Server),
+            any::<UserMask>().prop_map(Prefix::User),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for Command {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            (0..KnownCommand::ALL.len()).prop_map(|i| KnownCommand::ALL[i].into()),
+            (0..1000u16).prop_map(Command::Numeric),
+            "[a-zA-Z]{1,12}".prop_map(Command::General),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for Message {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    // anything over the length limits gets thrown out
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            option::of(vec(any::<Tag>(), 1..5)),
+            option::of(any::<Prefix>()),
+            any::<Command>(),
+            vec("[^\0\r\n :][^\0\r\n ]{0,15}", 0..=14),
+            option::of("[^\0\r\n]{0,40}"),
+        )
+            .prop_filter_map("too long", |(tags, prefix, command, parameters, last)| {
+                Message::new(prefix, command, parameters, last)
+                    .and_then(|m| m.with_tags(tags))
+                    .ok()
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{
+        arbitrary::any,
+        collection::vec,
+        prop_oneof, proptest,
+        strategy::{Just, Strategy},
+    };
+
+    use crate::{Message, MessageIter, MessageRef, Tag};
+
+    // keeps the byte inputs to a few likely-looking lines, rather than mostly being one long garbage line
+    fn line_bytes() -> impl Strategy<Value = Vec<u8>> {
+        vec(
+            prop_oneof![
+                4 => any::<u8>(),
+                1 => prop_oneof![
+                    Just(b' '),
+                    Just(b':'),
+                    Just(b'@'),
+                    Just(b';'),
+                    Just(b'='),
+                    Just(b'\r'),
+                    Just(b'\n'),
+                ],
+            ],
+            0..600,
+        )
+    }
+
+    // what round_trip relies on for empty tag values
+    #[test]
+    fn empty_tag_value_round_trip() {
+        let raw = b"@a=;b PING\r\n";
+        let (message, _) = Message::parse(raw).unwrap().unwrap();
+        assert_eq!(
+            &Some(vec![
+                Tag {
+                    key: "a".to_string(),
+                    value: Some(String::new())
+                },
+                Tag {
+                    key: "b".to_string(),
+                    value: None
+                },
+            ]),
+            message.tags()
+        );
+        assert_eq!(&raw[..], &message.to_bytes().unwrap()[..]);
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip(message in any::<Message>()) {
+            let bytes = message.to_bytes().unwrap();
+            let (parsed, size) = Message::parse(&bytes).unwrap().unwrap();
+            assert_eq!(bytes.len(), size);
+            assert_eq!(message, parsed);
+            assert_eq!(message.wire_len().total(), size);
+        }
+
+        #[test]
+        fn parse_never_panics(bytes in vec(any::<u8>(), 0..1200)) {
+            let _ = Message::parse(&bytes);
+            let _ = Message::parse_lenient(&bytes);
+            let _ = Message::parse_strict(&bytes);
+        }
+
+        #[test]
+        fn parse_lines_never_panics(bytes in line_bytes()) {
+            let _ = Message::parse(&bytes);
+            let _ = Message::parse_lenient(&bytes);
+            let _ = Message::parse_strict(&bytes);
+            let _ = Message::parse_raw(&bytes);
+            let _ = Message::parse_all(&bytes);
+            let _ = MessageRef::parse(&bytes);
+
+            let mut buffer = bytes::BytesMut::from(&bytes[..]);
+            for _ in MessageIter::new(&mut buffer) {}
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use crate::{KnownCommand, Message};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Batch {
+    pub reference: String,
+    // like `chathistory` or `netsplit`
+    pub kind: String,
+    pub parameters: Vec<String>,
+    // in the order received, where nested batches show up when they close
+    pub items: Vec<Batched>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Batched {
+    Message(Message),
+    Batch(Batch),
+}
+
+// buffers messages tagged with an open batch, handing back the whole batch once it closes
+#[derive(Debug, Clone, Default)]
+pub struct BatchTracker {
+    // the open batches, along with the batch they're nested in
+    open: HashMap<String, (Batch, Option<String>)>,
+}
+
+impl BatchTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // returns anything that's ready for the caller, which is either a complete top-level batch
+    // or a message that isn't part of any open batch
+    pub fn handle(&mut self, message: Message) -> Option<Batched> {
+        let parent = batch_tag(&message)
+            .filter(|r| self.open.contains_key(*r))
+            .map(|r| r.to_string());
+
+        if message.command().as_known() == Some(KnownCommand::Batch) {
+            if let Some((first, rest)) = message.params().split_first() {
+                if let Some(reference) = first.strip_prefix('+') {
+                    let batch = Batch {
+                        reference: reference.to_string(),
+                        kind: rest.first().cloned().unwrap_or_default(),
+                        parameters: rest.iter().skip(1).cloned().collect(),
+                        items: Vec::new(),
+                    };
+                    self.open.insert(reference.to_string(), (batch, parent));
+                    return None;
+                }
+
+                if let Some(reference) = first.strip_prefix('-') {
+                    // closing a batch we never saw open isn't worth surfacing
+                    let (batch, parent) = self.open.remove(reference)?;
+                    return self.add(parent, Batched::Batch(batch));
+                }
+            }
+        }
+
+        // orphaned references, to batches we don't know about, get passed through
+        self.add(parent, Batched::Message(message))
+    }
+
+    fn add(&mut self, parent: Option<String>, item: Batched) -> Option<Batched> {
+        match parent.and_then(|p| self.open.get_mut(&p)) {
+            Some((parent, _)) => {
+                parent.items.push(item);
+                None
+            }
+            None => Some(item),
+        }
+    }
+}
+
+fn batch_tag(message: &Message) -> Option<&str> {
+    message
+        .tags()
+        .as_ref()?
+        .iter()
+        .find(|t| t.key == "batch")?
+        .value
+        .as_deref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handle(tracker: &mut BatchTracker, raw: &str) -> Option<Batched> {
+        tracker.handle(raw.parse().unwrap())
+    }
+
+    #[test]
+    fn chathistory() {
+        let mut tracker = BatchTracker::new();
+
+        assert_eq!(
+            None,
+            handle(&mut tracker, ":irc.server BATCH +abc chathistory #chan")
+        );
+        assert_eq!(
+            None,
+            handle(&mut tracker, "@batch=abc :a!u@h PRIVMSG #chan :one")
+        );
+        // not part of the batch, so it comes right back
+        assert_eq!(
+            Some(Batched::Message("PING :now".parse().unwrap())),
+            handle(&mut tracker, "PING :now")
+        );
+        assert_eq!(
+            None,
+            handle(&mut tracker, "@batch=abc :b!u@h PRIVMSG #chan :two")
+        );
+
+        assert_eq!(
+            Some(Batched::Batch(Batch {
+                reference: "abc".to_string(),
+                kind: "chathistory".to_string(),
+                parameters: vec!["#chan".to_string()],
+                items: vec![
+                    Batched::Message("@batch=abc :a!u@h PRIVMSG #chan :one".parse().unwrap()),
+                    Batched::Message("@batch=abc :b!u@h PRIVMSG #chan :two".parse().unwrap()),
+                ],
+            })),
+            handle(&mut tracker, ":irc.server BATCH -abc")
+        );
+    }
+
+    #[test]
+    fn nested() {
+        let mut tracker = BatchTracker::new();
+
+        handle(&mut tracker, "BATCH +outer example");
+        handle(
+            &mut tracker,
+            "@batch=outer BATCH +inner netsplit a.server b.server",
+        );
+        handle(&mut tracker, "@batch=inner :a!u@h QUIT :a.server b.server");
+        assert_eq!(None, handle(&mut tracker, "BATCH -inner"));
+
+        let Some(Batched::Batch(outer)) = handle(&mut tracker, "BATCH -outer") else {
+            panic!("Outer batch not emitted")
+        };
+        let [Batched::Batch(inner)] = &outer.items[..] else {
+            panic!("Inner batch not nested")
+        };
+        assert_eq!("netsplit", inner.kind);
+        assert_eq!(vec!["a.server", "b.server"], inner.parameters);
+        assert_eq!(1, inner.items.len());
+    }
+
+    #[test]
+    fn orphans() {
+        let mut tracker = BatchTracker::new();
+
+        let orphan = "@batch=unknown PRIVMSG #chan :hi";
+        assert_eq!(
+            Some(Batched::Message(orphan.parse().unwrap())),
+            handle(&mut tracker, orphan)
+        );
+        assert_eq!(None, handle(&mut tracker, "BATCH -unknown"));
+    }
+}
@@ -0,0 +1,117 @@
+use crate::{KnownCommand, Message, Result};
+
+// the parts of a znc-style combined password, like `user/network:password`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BouncerLogin {
+    // may include a client id, like `user@client`, which is kept as-is
+    pub user: String,
+    pub network: Option<String>,
+    pub password: String,
+}
+
+impl BouncerLogin {
+    pub fn combined(&self) -> String {
+        match &self.network {
+            Some(network) => format!("{}/{}:{}", self.user, network, self.password),
+            None => format!("{}:{}", self.user, self.password),
+        }
+    }
+
+    // the password itself may have colons, but the user and network can't
+    pub fn split(combined: &str) -> Option<BouncerLogin> {
+        let (login, password) = combined.split_once(':')?;
+        let (user, network) = match login.split_once('/') {
+            Some((user, network)) => (user, Some(network.to_string())),
+            None => (login, None),
+        };
+        if user.is_empty() || network.as_deref() == Some("") {
+            return None;
+        }
+
+        Some(BouncerLogin {
+            user: user.to_string(),
+            network,
+            password: password.to_string(),
+        })
+    }
+
+    // also accepts PASS messages from servers that put the password in a middle parameter
+    pub fn from_pass(msg: &Message) -> Option<BouncerLogin> {
+        if msg.command().as_known() != Some(KnownCommand::Pass) {
+            return None;
+        }
+        let password = msg
+            .trailing()
+            .or_else(|| msg.params().first().map(|p| p.as_str()))?;
+        Self::split(password)
+    }
+
+    // for the bouncer's side, checking a client's login against what's configured
+    pub fn matches(&self, expected: &BouncerLogin) -> bool {
+        self.user == expected.user
+            && self.network == expected.network
+            && crate::secure::ct_eq(self.password.as_bytes(), expected.password.as_bytes())
+    }
+}
+
+pub fn pass(password: &str) -> Result<Message> {
+    Message::new(
+        None,
+        KnownCommand::Pass.into(),
+        None::<&str>,
+        Some(password),
+    )
+}
+
+pub fn bouncer_pass(login: &BouncerLogin) -> Result<Message> {
+    pass(&login.combined())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combined_round_trip() {
+        let login = BouncerLogin {
+            user: "user".to_string(),
+            network: Some("network".to_string()),
+            password: "pw".to_string(),
+        };
+
+        let message = bouncer_pass(&login).unwrap();
+        assert_eq!(
+            b"PASS :user/network:pw\r\n",
+            &message.to_bytes().unwrap()[..]
+        );
+        assert_eq!(Some(login), BouncerLogin::from_pass(&message));
+    }
+
+    #[test]
+    fn split_variants() {
+        let login = BouncerLogin::split("user@phone:pass:with:colons").unwrap();
+        assert_eq!("user@phone", login.user);
+        assert_eq!(None, login.network);
+        assert_eq!("pass:with:colons", login.password);
+        assert_eq!("user@phone:pass:with:colons", login.combined());
+
+        assert_eq!(None, BouncerLogin::split("justapassword"));
+        assert_eq!(None, BouncerLogin::split("user/:pw"));
+        assert_eq!(None, BouncerLogin::split(":pw"));
+    }
+
+    #[test]
+    fn matches() {
+        let expected = BouncerLogin::split("user/network:pw").unwrap();
+        assert!(BouncerLogin::split("user/network:pw")
+            .unwrap()
+            .matches(&expected));
+        assert!(!BouncerLogin::split("user/network:pw2")
+            .unwrap()
+            .matches(&expected));
+        assert!(!BouncerLogin::split("user/other:pw")
+            .unwrap()
+            .matches(&expected));
+        assert!(!BouncerLogin::split("user:pw").unwrap().matches(&expected));
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::HashSet;
+
+use crate::{KnownCommand, Message, Result};
+
+// drives CAP LS/REQ/ACK/NAK/END, where the caller sends whatever messages are handed back
+#[derive(Debug, Clone)]
+pub struct CapNegotiator {
+    desired: HashSet<String>,
+    available: HashSet<String>,
+    pending: HashSet<String>,
+    acknowledged: HashSet<String>,
+    complete: bool,
+}
+
+impl CapNegotiator {
+    pub fn new(desired: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        CapNegotiator {
+            desired: desired.into_iter().map(Into::into).collect(),
+            available: HashSet::new(),
+            pending: HashSet::new(),
+            acknowledged: HashSet::new(),
+            complete: false,
+        }
+    }
+
+    // sent before registration, so that the server holds off on completing it until CAP END
+    pub fn start(&self) -> Result<Message> {
+        cap_message(&["LS", "302"], None)
+    }
+
+    // returns the messages to send in response, if any
+    pub fn handle(&mut self, message: &Message) -> Result<Vec<Message>> {
+        if message.command().as_known() != Some(KnownCommand::Cap) {
+            return Ok(Vec::new());
+        }
+
+        let Some(parameters) = message.parameters() else {
+            return Ok(Vec::new());
+        };
+        // the first parameter is our nick, or * if we don't have one yet
+        let subcommand = parameters.get(1).map(|s| s.to_ascii_uppercase());
+        let caps = message
+            .last_parameter()
+            .as_deref()
+            .unwrap_or_default()
+            .split(' ')
+            .filter(|c| !c.is_empty());
+
+        match subcommand.as_deref() {
+            Some("LS") => {
+                // values, like `sasl=PLAIN,EXTERNAL`, aren't needed to decide what to request
+                self.available
+                    .extend(caps.map(|c| c.split_once('=').map_or(c, |(c, _)| c).to_string()));
+
+                // multiline responses have a * before the caps on every line but the last
+                if parameters.get(2).is_some_and(|p| p == "*") || self.complete {
+                    return Ok(Vec::new());
+                }
+
+                let mut requested = self
+                    .desired
+                    .intersection(&self.available)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if requested.is_empty() {
+                    return self.end();
+                }
+
+                requested.sort();
+                self.pending.extend(requested.iter().cloned());
+                Ok(vec![cap_message(&["REQ"], Some(requested.join(" ")))?])
+            }
+            Some("ACK") => {
+                for cap in caps {
+                    match cap.strip_prefix('-') {
+                        Some(cap) => self.acknowledged.remove(cap),
+                        None => self.acknowledged.insert(cap.to_string()),
+                    };
+                    self.pending.remove(cap.trim_start_matches('-'));
+                }
+                self.end_if_settled()
+            }
+            Some("NAK") => {
+                for cap in caps {
+                    self.pending.remove(cap);
+                }
+                self.end_if_settled()
+            }
+            // with cap-notify, caps can go away after negotiation
+            Some("DEL") => {
+                for cap in caps {
+                    self.acknowledged.remove(cap);
+                    self.available.remove(cap);
+                }
+                Ok(Vec::new())
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    pub fn acknowledged(&self) -> &HashSet<String> {
+        &self.acknowledged
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    fn end_if_settled(&mut self) -> Result<Vec<Message>> {
+        if self.pending.is_empty() && !self.complete {
+            self.end()
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    fn end(&mut self) -> Result<Vec<Message>> {
+        self.complete = true;
+        Ok(vec![cap_message(&["END"], None)?])
+    }
+}
+
+fn cap_message(parameters: &[&str], last_parameter: Option<String>) -> Result<Message> {
+    Message::new(
+        None,
+        KnownCommand::Cap.into(),
+        parameters.iter().copied(),
+        last_parameter,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handle(negotiator: &mut CapNegotiator, raw: &str) -> Vec<String> {
+        negotiator
+            .handle(&raw.parse().unwrap())
+            .unwrap()
+            .iter()
+            .map(|m| m.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn scripted_negotiation() {
+        let mut negotiator = CapNegotiator::new(["sasl", "server-time", "batch", "not-offered"]);
+        assert_eq!("CAP LS 302", negotiator.start().unwrap().to_string());
+
+        assert!(handle(
+            &mut negotiator,
+            ":irc.example.com CAP * LS * :multi-prefix sasl=PLAIN,EXTERNAL"
+        )
+        .is_empty());
+        assert_eq!(
+            vec!["CAP REQ :batch sasl server-time"],
+            handle(
+                &mut negotiator,
+                ":irc.example.com CAP * LS :server-time batch"
+            )
+        );
+        assert!(!negotiator.is_complete());
+
+        assert!(handle(
+            &mut negotiator,
+            ":irc.example.com CAP * ACK :sasl server-time"
+        )
+        .is_empty());
+        assert_eq!(
+            vec!["CAP END"],
+            handle(&mut negotiator, ":irc.example.com CAP * NAK :batch")
+        );
+        assert!(negotiator.is_complete());
+        assert_eq!(
+            &HashSet::from(["sasl".to_string(), "server-time".to_string()]),
+            negotiator.acknowledged()
+        );
+
+        handle(&mut negotiator, ":irc.example.com CAP nick DEL :sasl");
+        assert_eq!(
+            &HashSet::from(["server-time".to_string()]),
+            negotiator.acknowledged()
+        );
+    }
+
+    #[test]
+    fn nothing_to_request() {
+        let mut negotiator = CapNegotiator::new(["sasl"]);
+
+        assert_eq!(
+            vec!["CAP END"],
+            handle(&mut negotiator, ":irc.example.com CAP * LS :multi-prefix")
+        );
+        assert!(negotiator.is_complete());
+        assert!(negotiator.acknowledged().is_empty());
+    }
+
+    #[test]
+    fn ignores_other_commands() {
+        let mut negotiator = CapNegotiator::new(["sasl"]);
+
+        assert!(handle(&mut negotiator, "PING :sasl").is_empty());
+        assert!(!negotiator.is_complete());
+    }
+}
@@ -0,0 +1,79 @@
+use alloc::string::String;
+
+// how servers compare nicks and channels, as advertised by the CASEMAPPING ISUPPORT token
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CaseMapping {
+    // only a-z and A-Z
+    Ascii,
+    // also treats []\~ as the uppercase versions of {}|^, and is what servers use if they don't say otherwise
+    #[default]
+    Rfc1459,
+    // same as rfc1459, but without ~ and ^
+    Strict,
+}
+
+impl CaseMapping {
+    // the value of the CASEMAPPING token, like `rfc1459`
+    pub fn from_isupport(value: &str) -> Option<CaseMapping> {
+        match value {
+            "ascii" => Some(CaseMapping::Ascii),
+            "rfc1459" => Some(CaseMapping::Rfc1459),
+            "strict-rfc1459" => Some(CaseMapping::Strict),
+            _ => None,
+        }
+    }
+
+    pub fn fold_char(self, c: char) -> char {
+        match (self, c) {
+            (CaseMapping::Rfc1459 | CaseMapping::Strict, '[') => '{',
+            (CaseMapping::Rfc1459 | CaseMapping::Strict, ']') => '}',
+            (CaseMapping::Rfc1459 | CaseMapping::Strict, '\\') => '|',
+            (CaseMapping::Rfc1459, '~') => '^',
+            (_, c) => c.to_ascii_lowercase(),
+        }
+    }
+
+    pub fn fold(self, s: &str) -> String {
+        s.chars().map(|c| self.fold_char(c)).collect()
+    }
+
+    pub fn eq(self, a: &str, b: &str) -> bool {
+        a.chars()
+            .map(|c| self.fold_char(c))
+            .eq(b.chars().map(|c| self.fold_char(c)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc1459_vs_ascii() {
+        assert!(CaseMapping::Rfc1459.eq("[Nick]", "{nick}"));
+        assert!(!CaseMapping::Ascii.eq("[Nick]", "{nick}"));
+        assert!(CaseMapping::Ascii.eq("#Chan", "#chan"));
+        assert_eq!("{nick}|^", CaseMapping::Rfc1459.fold("[NICK]\\~"));
+    }
+
+    #[test]
+    fn strict() {
+        assert!(CaseMapping::Strict.eq("a\\b", "A|B"));
+        assert!(!CaseMapping::Strict.eq("a~", "a^"));
+        assert!(CaseMapping::Rfc1459.eq("a~", "a^"));
+    }
+
+    #[test]
+    fn from_isupport() {
+        assert_eq!(
+            Some(CaseMapping::Ascii),
+            CaseMapping::from_isupport("ascii")
+        );
+        assert_eq!(
+            Some(CaseMapping::Strict),
+            CaseMapping::from_isupport("strict-rfc1459")
+        );
+        assert_eq!(None, CaseMapping::from_isupport("rfc7613"));
+        assert_eq!(CaseMapping::Rfc1459, CaseMapping::default());
+    }
+}
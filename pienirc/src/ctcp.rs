@@ -0,0 +1,143 @@
+// only the low-level quoting is handled here.
+// the ctcp-level backslash quoting from the original spec is effectively unimplemented by clients,
+// and applying it would mangle otherwise normal text like windows paths.
+const DELIMITER: char = '\x01';
+const QUOTE: char = '\x10';
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ctcp {
+    pub command: String,
+    pub args: Option<String>,
+}
+
+pub fn encode(command: &str, args: Option<&str>) -> String {
+    let mut s = String::with_capacity(command.len() + args.map_or(0, |a| a.len() + 1) + 2);
+
+    s.push(DELIMITER);
+    quote(command, &mut s);
+    if let Some(a) = args {
+        s.push(' ');
+        quote(a, &mut s);
+    }
+    s.push(DELIMITER);
+
+    s
+}
+
+pub fn decode(trailing: &str) -> Option<Ctcp> {
+    let body = trailing.strip_prefix(DELIMITER)?;
+    // plenty of clients leave off the closing delimiter
+    let body = body.strip_suffix(DELIMITER).unwrap_or(body);
+    let body = dequote(body);
+
+    let (command, args) = match body.split_once(' ') {
+        Some((c, a)) => (c, Some(a.to_string())),
+        None => (&body[..], None),
+    };
+
+    if command.is_empty() {
+        return None;
+    }
+
+    Some(Ctcp {
+        command: command.to_string(),
+        args,
+    })
+}
+
+fn quote(text: &str, s: &mut String) {
+    for c in text.chars() {
+        match c {
+            '\0' => s.push_str("\x100"),
+            '\n' => s.push_str("\x10n"),
+            '\r' => s.push_str("\x10r"),
+            QUOTE => s.push_str("\x10\x10"),
+            c => s.push(c),
+        }
+    }
+}
+
+fn dequote(text: &str) -> String {
+    let mut s = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != QUOTE {
+            s.push(c);
+            continue;
+        }
+
+        // unknown quotes drop the quote char, and a trailing quote char is dropped entirely
+        match chars.next() {
+            Some('0') => s.push('\0'),
+            Some('n') => s.push('\n'),
+            Some('r') => s.push('\r'),
+            Some(c) => s.push(c),
+            None => (),
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn action() {
+        let encoded = encode("ACTION", Some("waves"));
+        assert_eq!("\x01ACTION waves\x01", encoded);
+
+        assert_eq!(
+            Some(Ctcp {
+                command: "ACTION".to_string(),
+                args: Some("waves".to_string())
+            }),
+            decode(&encoded)
+        );
+    }
+
+    #[test]
+    fn version_without_args() {
+        assert_eq!("\x01VERSION\x01", encode("VERSION", None));
+        assert_eq!(
+            Some(Ctcp {
+                command: "VERSION".to_string(),
+                args: None
+            }),
+            decode("\x01VERSION\x01")
+        );
+    }
+
+    #[test]
+    fn quoted_ping() {
+        let payload = "123\0 4\x10\r\n56";
+        let encoded = encode("PING", Some(payload));
+        assert_eq!("\x01PING 123\x100 4\x10\x10\x10r\x10n56\x01", encoded);
+
+        assert_eq!(
+            Some(Ctcp {
+                command: "PING".to_string(),
+                args: Some(payload.to_string())
+            }),
+            decode(&encoded)
+        );
+    }
+
+    #[test]
+    fn missing_closing_delimiter() {
+        assert_eq!(
+            Some(Ctcp {
+                command: "ACTION".to_string(),
+                args: Some("waves".to_string())
+            }),
+            decode("\x01ACTION waves")
+        );
+    }
+
+    #[test]
+    fn not_ctcp() {
+        assert_eq!(None, decode("hello there"));
+        assert_eq!(None, decode(""));
+        assert_eq!(None, decode("\x01\x01"));
+    }
+}
@@ -0,0 +1,97 @@
+use std::{iter::Peekable, str::Chars};
+
+pub const BOLD: char = '\x02';
+pub const COLOR: char = '\x03';
+pub const MONOSPACE: char = '\x11';
+pub const REVERSE: char = '\x16';
+pub const ITALICS: char = '\x1d';
+pub const STRIKETHROUGH: char = '\x1e';
+pub const UNDERLINE: char = '\x1f';
+pub const RESET: char = '\x0f';
+
+// always two digits, so that text starting with a digit isn't mistaken for part of the color.
+// codes past 99 don't exist, so they're clamped.
+pub fn color(fg: u8, bg: Option<u8>) -> String {
+    match bg {
+        Some(bg) => format!("{}{:02},{:02}", COLOR, fg.min(99), bg.min(99)),
+        None => format!("{}{:02}", COLOR, fg.min(99)),
+    }
+}
+
+// removes mirc-style formatting, including the color numbers that follow a color code
+pub fn strip(text: &str) -> String {
+    let mut s = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            COLOR => {
+                // \x03N[N][,M[M]], where a comma not followed by a digit is normal text
+                if skip_color_number(&mut chars) > 0 {
+                    let mut lookahead = chars.clone();
+                    if lookahead.next() == Some(',')
+                        && lookahead.peek().is_some_and(|c| c.is_ascii_digit())
+                    {
+                        chars.next();
+                        skip_color_number(&mut chars);
+                    }
+                }
+            }
+            BOLD | MONOSPACE | REVERSE | ITALICS | STRIKETHROUGH | UNDERLINE | RESET => (),
+            c => s.push(c),
+        }
+    }
+
+    s
+}
+
+fn skip_color_number(chars: &mut Peekable<Chars>) -> usize {
+    let mut count = 0;
+    while count < 2 && chars.next_if(|c| c.is_ascii_digit()).is_some() {
+        count += 1;
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_codes() {
+        assert_eq!("\u{3}04", color(4, None));
+        assert_eq!("\u{3}04,12", color(4, Some(12)));
+        assert_eq!("\u{3}99,99", color(150, Some(255)));
+        assert_eq!(
+            "\x02\x0304,01warning\x0f",
+            format!("{}{}warning{}", BOLD, color(4, Some(1)), RESET)
+        );
+    }
+
+    #[test]
+    fn strip_built_text() {
+        let text = format!("{}{}5 alarm fire{}", BOLD, color(4, None), RESET);
+        assert_eq!("5 alarm fire", strip(&text));
+    }
+
+    #[test]
+    fn strip_bold() {
+        assert_eq!("some bold text", strip("some \x02bold\x02 text"));
+    }
+
+    #[test]
+    fn strip_colors() {
+        assert_eq!("red text", strip("\x034red text"));
+        assert_eq!("red on blue", strip("\x0304,12red on blue\x03"));
+        assert_eq!("123", strip("\x0312123"));
+        // the comma isn't part of the color unless a digit follows
+        assert_eq!(",not bg", strip("\x034,not bg"));
+        assert_eq!(",5", strip("\x03,5"));
+    }
+
+    #[test]
+    fn strip_reset() {
+        assert_eq!("plain", strip("\x0fplain"));
+        assert_eq!("all of them", strip("\x1dall\x1f of\x16 them\x0f\x11\x1e"));
+    }
+}
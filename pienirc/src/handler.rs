@@ -0,0 +1,96 @@
+use crate::{Command, KnownCommand, Message, Prefix};
+
+// implementors only override what they care about, and dispatch routes each message to the right method.
+// messages missing the parameters their command needs end up in on_other.
+pub trait Handler {
+    fn on_privmsg(&mut self, _from: Option<&Prefix>, _target: &str, _text: &str) {}
+    fn on_notice(&mut self, _from: Option<&Prefix>, _target: &str, _text: &str) {}
+    fn on_join(&mut self, _from: Option<&Prefix>, _channel: &str) {}
+    fn on_part(&mut self, _from: Option<&Prefix>, _channel: &str, _reason: Option<&str>) {}
+    fn on_ping(&mut self, _token: &str) {}
+    fn on_numeric(&mut self, _numeric: u16, _msg: &Message) {}
+    fn on_other(&mut self, _msg: &Message) {}
+
+    fn dispatch(&mut self, msg: &Message) {
+        let from = msg.prefix_ref();
+        let parameters = msg.all_params().collect::<Vec<_>>();
+
+        if let Command::Numeric(n) = msg.command() {
+            return self.on_numeric(*n, msg);
+        }
+
+        match (msg.command().as_known(), &parameters[..]) {
+            (Some(KnownCommand::Privmsg), [target, text]) => self.on_privmsg(from, target, text),
+            (Some(KnownCommand::Notice), [target, text]) => self.on_notice(from, target, text),
+            (Some(KnownCommand::Join), [channel, ..]) => self.on_join(from, channel),
+            (Some(KnownCommand::Part), [channel]) => self.on_part(from, channel, None),
+            (Some(KnownCommand::Part), [channel, reason]) => {
+                self.on_part(from, channel, Some(reason))
+            }
+            (Some(KnownCommand::Ping), [token, ..]) => self.on_ping(token),
+            _ => self.on_other(msg),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numerics;
+
+    #[derive(Default)]
+    struct Counter {
+        privmsgs: Vec<String>,
+        joins: Vec<String>,
+        pings: usize,
+        numerics: Vec<u16>,
+        others: usize,
+    }
+
+    impl Handler for Counter {
+        fn on_privmsg(&mut self, from: Option<&Prefix>, target: &str, text: &str) {
+            assert!(from.is_some());
+            self.privmsgs.push(format!("{target} {text}"));
+        }
+
+        fn on_join(&mut self, _from: Option<&Prefix>, channel: &str) {
+            self.joins.push(channel.to_string());
+        }
+
+        fn on_ping(&mut self, _token: &str) {
+            self.pings += 1;
+        }
+
+        fn on_numeric(&mut self, numeric: u16, _msg: &Message) {
+            self.numerics.push(numeric);
+        }
+
+        fn on_other(&mut self, _msg: &Message) {
+            self.others += 1;
+        }
+    }
+
+    #[test]
+    fn counting_handler() {
+        let mut counter = Counter::default();
+        for raw in [
+            ":irc.server 001 me :Welcome",
+            ":me!user@host JOIN #chan",
+            ":nick!user@host PRIVMSG #chan :hello there",
+            ":nick!user@host privmsg me hi",
+            "PING :abc",
+            ":nick!user@host NOTICE #chan :unhandled, so ignored",
+            ":nick!user@host PRIVMSG #chan",
+            ":nick!user@host FOO bar",
+        ] {
+            counter.dispatch(&raw.parse().unwrap());
+        }
+
+        assert_eq!(vec!["#chan hello there", "me hi"], counter.privmsgs);
+        assert_eq!(vec!["#chan"], counter.joins);
+        assert_eq!(1, counter.pings);
+        assert_eq!(vec![numerics::RPL_WELCOME], counter.numerics);
+        // the PRIVMSG without text, and FOO
+        assert_eq!(2, counter.others);
+    }
+}
@@ -0,0 +1,99 @@
+// conversions to and from the irc crate's messages, for migrating bots over a piece at a time
+use crate::{Command, Limits, Message, Prefix, Tag, UserMask};
+
+impl From<irc_proto::Message> for Message {
+    fn from(msg: irc_proto::Message) -> Self {
+        // the irc crate has an enum variant per command, so going through its serialization
+        // is the only general way of getting at the parameters
+        let command = String::from(&msg.command);
+        let unlimited = Limits {
+            max_line: usize::MAX,
+            max_tags: usize::MAX,
+        };
+        let parsed = Message::parse_with_limits(format!("{command}\r\n").as_bytes(), unlimited)
+            .ok()
+            .flatten()
+            .map(|(m, _)| m)
+            // only a raw command with an empty name can fail to parse
+            .unwrap_or_else(|| Message::new_unchecked(None, Command::General(command), None, None));
+
+        Message {
+            tags: msg.tags.map(|tags| {
+                tags.into_iter()
+                    .map(|irc_proto::message::Tag(key, value)| Tag { key, value })
+                    .collect()
+            }),
+            prefix: msg.prefix.map(|prefix| match prefix {
+                irc_proto::Prefix::ServerName(server) => Prefix::Server(server),
+                irc_proto::Prefix::Nickname(nickname, user, host) => Prefix::User(UserMask {
+                    nickname,
+                    user: Some(user).filter(|u| !u.is_empty()),
+                    server: Some(host).filter(|h| !h.is_empty()),
+                }),
+            }),
+            ..parsed
+        }
+    }
+}
+
+impl TryFrom<Message> for irc_proto::Message {
+    type Error = irc_proto::error::MessageParseError;
+
+    fn try_from(msg: Message) -> Result<Self, Self::Error> {
+        let name = match msg.command() {
+            Command::General(c) => c.clone(),
+            Command::Numeric(n) => format!("{n:03}"),
+        };
+        let arguments = msg.all_params().collect();
+        let command = irc_proto::Command::new(&name, arguments)?;
+
+        Ok(irc_proto::Message {
+            tags: msg.tags.map(|tags| {
+                tags.into_iter()
+                    .map(|t| irc_proto::message::Tag(t.key, t.value))
+                    .collect()
+            }),
+            prefix: msg.prefix.map(|prefix| match prefix {
+                Prefix::Server(server) => irc_proto::Prefix::ServerName(server),
+                Prefix::User(mask) => irc_proto::Prefix::Nickname(
+                    mask.nickname,
+                    mask.user.unwrap_or_default(),
+                    mask.server.unwrap_or_default(),
+                ),
+            }),
+            command,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn privmsg_round_trip() {
+        let irc: irc_proto::Message = "@id=1 :nick!user@host PRIVMSG #chan :hello there\r\n"
+            .parse()
+            .unwrap();
+
+        let message = Message::from(irc.clone());
+        let expected: Message = "@id=1 :nick!user@host PRIVMSG #chan :hello there"
+            .parse()
+            .unwrap();
+        assert_eq!(expected, message);
+
+        assert_eq!(irc, irc_proto::Message::try_from(message).unwrap());
+    }
+
+    #[test]
+    fn numeric_round_trip() {
+        // the irc crate only adds the colon when it's needed, so a single word trailing parameter would come back as a middle one
+        let message: Message = ":irc.server 001 me :Welcome to IRC".parse().unwrap();
+        let irc = irc_proto::Message::try_from(message.clone()).unwrap();
+        assert_eq!(
+            Some(&irc_proto::Prefix::ServerName("irc.server".to_string())),
+            irc.prefix.as_ref()
+        );
+        assert_eq!(message, Message::from(irc));
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{numerics, Command, Message};
+
+// tokens without a value, like `EXCEPTS`, are Some(""), leaving None for negated tokens like `-EXCEPTS`
+pub fn parse_isupport(msg: &Message) -> Vec<(String, Option<String>)> {
+    if msg.command() != &Command::Numeric(numerics::RPL_ISUPPORT) {
+        return Vec::new();
+    }
+
+    let Some(parameters) = msg.parameters() else {
+        return Vec::new();
+    };
+
+    // the first parameter is our nick, and the trailing parameter is the "are supported" blurb
+    parameters
+        .iter()
+        .skip(1)
+        .map(|p| match p.strip_prefix('-') {
+            Some(key) => (key.to_string(), None),
+            None => match p.split_once('=') {
+                Some((key, value)) => (key.to_string(), Some(unescape_value(value))),
+                None => (p.to_string(), Some(String::new())),
+            },
+        })
+        .collect()
+}
+
+// the PREFIX token, like `(ov)@+`, pairing each prefix mode with the symbol shown in NAMES and WHO replies.
+// the modes are in order of rank, highest first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixMap {
+    // (mode, symbol) pairs, like ('o', '@')
+    pub prefixes: Vec<(char, char)>,
+}
+
+impl PrefixMap {
+    // a malformed value ends up with no prefixes
+    pub fn from_isupport(value: &str) -> PrefixMap {
+        let prefixes = value
+            .strip_prefix('(')
+            .and_then(|v| v.split_once(')'))
+            .filter(|(modes, symbols)| modes.chars().count() == symbols.chars().count())
+            .map_or_else(Vec::new, |(modes, symbols)| {
+                modes.chars().zip(symbols.chars()).collect()
+            });
+
+        PrefixMap { prefixes }
+    }
+
+    pub fn mode_for(&self, symbol: char) -> Option<char> {
+        self.prefixes
+            .iter()
+            .find(|(_, s)| *s == symbol)
+            .map(|(m, _)| *m)
+    }
+}
+
+impl Default for PrefixMap {
+    // what servers are assumed to support if they don't advertise anything
+    fn default() -> Self {
+        PrefixMap::from_isupport("(ov)@+")
+    }
+}
+
+// values can have `\xHH` escapes, mainly for spaces
+fn unescape_value(value: &str) -> String {
+    let value = value.as_bytes();
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut i = 0;
+    while i < value.len() {
+        let escaped = Some(&value[i..])
+            .filter(|v| v.starts_with(b"\\x"))
+            .and_then(|v| v.get(2..4))
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+
+        match escaped {
+            Some(e) => {
+                bytes.push(e);
+                i += 4;
+            }
+            None => {
+                bytes.push(value[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn libera() {
+        let message: Message = ":tantalum.libera.chat 005 nick CALLERID=g WHOX ETRACE FNC SAFELIST ELIST=CMNTU KNOCK MONITOR=100 CHANTYPES=# EXCEPTS INVEX CHANMODES=eIbq,k,flj,CFLMPQRSTcgimnprstuz :are supported by this server"
+            .parse()
+            .unwrap();
+
+        let some = |k: &str, v: &str| (k.to_string(), Some(v.to_string()));
+        assert_eq!(
+            vec![
+                some("CALLERID", "g"),
+                some("WHOX", ""),
+                some("ETRACE", ""),
+                some("FNC", ""),
+                some("SAFELIST", ""),
+                some("ELIST", "CMNTU"),
+                some("KNOCK", ""),
+                some("MONITOR", "100"),
+                some("CHANTYPES", "#"),
+                some("EXCEPTS", ""),
+                some("INVEX", ""),
+                some("CHANMODES", "eIbq,k,flj,CFLMPQRSTcgimnprstuz"),
+            ],
+            parse_isupport(&message)
+        );
+    }
+
+    #[test]
+    fn negation_and_escapes() {
+        let message: Message =
+            ":server 005 nick -EXCEPTS NETWORK=Example\\x20Net :are supported by this server"
+                .parse()
+                .unwrap();
+
+        assert_eq!(
+            vec![
+                ("EXCEPTS".to_string(), None),
+                ("NETWORK".to_string(), Some("Example Net".to_string())),
+            ],
+            parse_isupport(&message)
+        );
+    }
+
+    #[test]
+    fn prefix_map() {
+        let prefixes = PrefixMap::from_isupport("(qaohv)~&@%+");
+        assert_eq!(Some('q'), prefixes.mode_for('~'));
+        assert_eq!(Some('v'), prefixes.mode_for('+'));
+        assert_eq!(None, prefixes.mode_for('!'));
+
+        assert_eq!(vec![('o', '@'), ('v', '+')], PrefixMap::default().prefixes);
+        assert!(PrefixMap::from_isupport("(ov)@").prefixes.is_empty());
+        assert!(PrefixMap::from_isupport("").prefixes.is_empty());
+    }
+
+    #[test]
+    fn not_isupport() {
+        let message: Message = ":server 001 nick :Welcome".parse().unwrap();
+        assert!(parse_isupport(&message).is_empty());
+    }
+}
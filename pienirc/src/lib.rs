@@ -1,2 +1,48 @@
+// only the core message handling works without std, for embedded clients
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "proptest")]
+mod arbitrary;
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod bouncer;
+#[cfg(feature = "std")]
+pub mod cap;
+pub mod casemap;
+#[cfg(feature = "std")]
+pub mod ctcp;
+#[cfg(feature = "std")]
+pub mod formatting;
+#[cfg(feature = "std")]
+pub mod handler;
+#[cfg(feature = "irc-compat")]
+mod irc_compat;
+#[cfg(feature = "std")]
+pub mod isupport;
+#[cfg(feature = "std")]
+pub mod mode;
+#[cfg(feature = "std")]
+pub mod monitor;
+#[cfg(feature = "std")]
+pub mod names;
+#[cfg(feature = "std")]
+pub mod nick;
+#[cfg(feature = "std")]
+pub mod notify;
+#[cfg(feature = "std")]
+pub mod numerics;
 pub mod protocol;
+#[cfg(feature = "std")]
+pub mod registration;
+#[cfg(feature = "std")]
+pub mod sasl;
+pub mod secure;
+#[cfg(feature = "std")]
+pub mod standard_reply;
+pub mod validate;
+#[cfg(feature = "std")]
+pub mod who;
 pub use protocol::*;
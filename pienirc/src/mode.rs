@@ -0,0 +1,132 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModeChange {
+    pub add: bool,
+    pub mode: char,
+    pub arg: Option<String>,
+}
+
+// which modes consume an argument, generally from the ISUPPORT CHANMODES and PREFIX tokens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamModes {
+    // list modes, modes that always take a parameter, and prefix modes like o and v
+    pub always: String,
+    // modes like l, that only take a parameter when being set
+    pub when_set: String,
+}
+
+impl ParamModes {
+    // chanmodes is the value of CHANMODES, like `beI,k,l,imnpst`,
+    // and prefix_modes is the mode part of PREFIX, like `ov` from `(ov)@+`
+    pub fn from_isupport(chanmodes: &str, prefix_modes: &str) -> ParamModes {
+        let mut groups = chanmodes.split(',');
+        let list = groups.next().unwrap_or_default();
+        let always = groups.next().unwrap_or_default();
+        let when_set = groups.next().unwrap_or_default();
+
+        ParamModes {
+            always: [list, always, prefix_modes].concat(),
+            when_set: when_set.to_string(),
+        }
+    }
+
+    fn takes_arg(&self, mode: char, add: bool) -> bool {
+        self.always.contains(mode) || (add && self.when_set.contains(mode))
+    }
+}
+
+impl Default for ParamModes {
+    // what servers are assumed to support if they don't advertise anything
+    fn default() -> Self {
+        ParamModes::from_isupport("beI,k,l,imnpst", "ov")
+    }
+}
+
+// if there are fewer args than modes that need them, the remaining ones get None,
+// which is what happens when, for instance, listing bans with `MODE #chan +b`
+pub fn parse(flags: &str, args: &[String], param_modes: &ParamModes) -> Vec<ModeChange> {
+    let mut args = args.iter();
+    let mut add = true;
+
+    flags
+        .chars()
+        .filter_map(|c| match c {
+            '+' => {
+                add = true;
+                None
+            }
+            '-' => {
+                add = false;
+                None
+            }
+            mode => Some(ModeChange {
+                add,
+                mode,
+                arg: param_modes
+                    .takes_arg(mode, add)
+                    .then(|| args.next().cloned())
+                    .flatten(),
+            }),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(add: bool, mode: char, arg: Option<&str>) -> ModeChange {
+        ModeChange {
+            add,
+            mode,
+            arg: arg.map(|a| a.to_string()),
+        }
+    }
+
+    #[test]
+    fn mixed_modes() {
+        let args = ["nick1", "nick2", "*!*@host"].map(|a| a.to_string());
+
+        assert_eq!(
+            vec![
+                change(true, 'o', Some("nick1")),
+                change(false, 'v', Some("nick2")),
+                change(true, 'b', Some("*!*@host")),
+            ],
+            parse("+o-v+b", &args, &ParamModes::default())
+        );
+    }
+
+    #[test]
+    fn no_args() {
+        assert_eq!(
+            vec![change(true, 'm', None)],
+            parse("+m", &[], &ParamModes::default())
+        );
+    }
+
+    #[test]
+    fn limit_only_takes_arg_when_set() {
+        let args = ["10".to_string()];
+
+        assert_eq!(
+            vec![change(false, 'l', None), change(true, 'l', Some("10"))],
+            parse("-l+l", &args, &ParamModes::default())
+        );
+    }
+
+    #[test]
+    fn from_isupport() {
+        let modes = ParamModes::from_isupport("eIbq,k,flj,CFLMPQRSTcgimnprstuz", "ov");
+        let args = ["*!*@host", "5", "nick"].map(|a| a.to_string());
+
+        assert_eq!(
+            vec![
+                change(true, 'q', Some("*!*@host")),
+                change(true, 'j', Some("5")),
+                change(true, 'n', None),
+                change(false, 'o', Some("nick")),
+            ],
+            parse("+qjn-o", &args, &modes)
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{numerics, Command, Error, Message, Result};
+
+// like `MONITOR + nick1,nick2`
+pub fn add(nicks: &[&str]) -> Result<Message> {
+    with_targets("+", nicks)
+}
+
+pub fn remove(nicks: &[&str]) -> Result<Message> {
+    with_targets("-", nicks)
+}
+
+pub fn clear() -> Result<Message> {
+    monitor_message(["C"])
+}
+
+// the server replies with RPL_MONLIST, then RPL_ENDOFMONLIST
+pub fn list() -> Result<Message> {
+    monitor_message(["L"])
+}
+
+// the server replies with RPL_MONONLINE and RPL_MONOFFLINE for everything being monitored
+pub fn status() -> Result<Message> {
+    monitor_message(["S"])
+}
+
+// RPL_MONONLINE, like `:server 730 me :nick!user@host,other!user@host`, as the nicks that came online
+pub fn parse_730(msg: &Message) -> Option<Vec<String>> {
+    parse_targets(msg, numerics::RPL_MONONLINE)
+}
+
+// RPL_MONOFFLINE, like `:server 731 me :nick,other`, as the nicks that went offline
+pub fn parse_731(msg: &Message) -> Option<Vec<String>> {
+    parse_targets(msg, numerics::RPL_MONOFFLINE)
+}
+
+fn with_targets(subcommand: &str, nicks: &[&str]) -> Result<Message> {
+    // a comma would turn one nick into two
+    if nicks.is_empty() || nicks.iter().any(|n| n.is_empty() || n.contains(',')) {
+        return Err(Error::CommandValidation);
+    }
+    monitor_message([subcommand, &nicks.join(",")])
+}
+
+fn monitor_message<'a>(parameters: impl IntoIterator<Item = &'a str>) -> Result<Message> {
+    Message::new(
+        None,
+        Command::General("MONITOR".to_string()),
+        parameters,
+        None::<&str>,
+    )
+}
+
+// the targets may be full masks or just nicks, depending on the server
+fn parse_targets(msg: &Message, numeric: u16) -> Option<Vec<String>> {
+    if msg.command() != &Command::Numeric(numeric) {
+        return None;
+    }
+
+    // the first parameter is our nick
+    let targets = msg.all_params().nth(1)?;
+    Some(
+        targets
+            .split(',')
+            .filter(|t| !t.is_empty())
+            .map(|t| t.split_once('!').map_or(t, |(nick, _)| nick).to_string())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_messages() {
+        assert_eq!(
+            b"MONITOR + alice,bob\r\n",
+            &add(&["alice", "bob"]).unwrap().to_bytes().unwrap()[..]
+        );
+        assert_eq!(
+            b"MONITOR - alice\r\n",
+            &remove(&["alice"]).unwrap().to_bytes().unwrap()[..]
+        );
+        assert_eq!(b"MONITOR C\r\n", &clear().unwrap().to_bytes().unwrap()[..]);
+        assert_eq!(b"MONITOR L\r\n", &list().unwrap().to_bytes().unwrap()[..]);
+
+        assert!(matches!(add(&[]), Err(Error::CommandValidation)));
+        assert!(matches!(add(&["a,b"]), Err(Error::CommandValidation)));
+    }
+
+    #[test]
+    fn online_and_offline() {
+        let message: Message = ":irc.server 730 me :alice!a@host,bob!b@host"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            Some(vec!["alice".to_string(), "bob".to_string()]),
+            parse_730(&message)
+        );
+        assert_eq!(None, parse_731(&message));
+
+        let message: Message = ":irc.server 731 me carol".parse().unwrap();
+        assert_eq!(Some(vec!["carol".to_string()]), parse_731(&message));
+    }
+}
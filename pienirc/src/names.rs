@@ -0,0 +1,116 @@
+use crate::{isupport::PrefixMap, numerics, Command, Message};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    pub name: String,
+    // like 'o' for `@nick`
+    pub modes: Vec<char>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamesReply {
+    // `=` for public, `*` for private, and `@` for secret channels
+    pub visibility: char,
+    pub channel: String,
+    pub members: Vec<Member>,
+}
+
+// RPL_NAMREPLY, like `:server 353 me = #chan :@op +voiced normal`
+pub fn parse_353(msg: &Message, prefixes: &PrefixMap) -> Option<NamesReply> {
+    if msg.command() != &Command::Numeric(numerics::RPL_NAMREPLY) {
+        return None;
+    }
+
+    // the first parameter is our nick. some older servers leave out the visibility symbol.
+    let (visibility, channel) = match msg.params() {
+        [_, visibility, channel] => (visibility.chars().next()?, channel),
+        [_, channel] => ('=', channel),
+        _ => return None,
+    };
+
+    let members = msg
+        .trailing()
+        .unwrap_or_default()
+        .split(' ')
+        .filter(|m| !m.is_empty())
+        .map(|m| {
+            // with multi-prefix, there can be more than one symbol
+            let name = m.trim_start_matches(|c| prefixes.mode_for(c).is_some());
+            Member {
+                name: name.to_string(),
+                modes: m[..m.len() - name.len()]
+                    .chars()
+                    .filter_map(|c| prefixes.mode_for(c))
+                    .collect(),
+            }
+        })
+        .collect();
+
+    Some(NamesReply {
+        visibility,
+        channel: channel.to_string(),
+        members,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mixed_prefixes() {
+        let message: Message = ":irc.server 353 me = #chan :@op +voiced normal @+both"
+            .parse()
+            .unwrap();
+
+        let member = |name: &str, modes: &[char]| Member {
+            name: name.to_string(),
+            modes: modes.to_vec(),
+        };
+        assert_eq!(
+            Some(NamesReply {
+                visibility: '=',
+                channel: "#chan".to_string(),
+                members: vec![
+                    member("op", &['o']),
+                    member("voiced", &['v']),
+                    member("normal", &[]),
+                    member("both", &['o', 'v']),
+                ],
+            }),
+            parse_353(&message, &PrefixMap::default())
+        );
+    }
+
+    #[test]
+    fn multi_prefix() {
+        let message: Message = ":irc.server 353 me = #chan :@+nick other".parse().unwrap();
+
+        let reply = parse_353(&message, &PrefixMap::default()).unwrap();
+        assert_eq!("nick", reply.members[0].name);
+        assert_eq!(vec!['o', 'v'], reply.members[0].modes);
+        assert_eq!("other", reply.members[1].name);
+        assert!(reply.members[1].modes.is_empty());
+    }
+
+    #[test]
+    fn custom_prefixes() {
+        let message: Message = ":irc.server 353 me @ #secret :~owner %halfop"
+            .parse()
+            .unwrap();
+
+        let reply = parse_353(&message, &PrefixMap::from_isupport("(qohv)~@%+")).unwrap();
+        assert_eq!('@', reply.visibility);
+        assert_eq!(vec!['q'], reply.members[0].modes);
+        assert_eq!("halfop", reply.members[1].name);
+        assert_eq!(vec!['h'], reply.members[1].modes);
+    }
+
+    #[test]
+    fn not_names() {
+        let message: Message = ":irc.server 366 me #chan :End of /NAMES list."
+            .parse()
+            .unwrap();
+        assert_eq!(None, parse_353(&message, &PrefixMap::default()));
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::VecDeque;
+
+use crate::{numerics, KnownCommand, Message};
+
+// proposes another nick each time the server says the last one is taken
+#[derive(Debug, Clone)]
+pub struct NickFallback {
+    remaining: VecDeque<String>,
+}
+
+impl NickFallback {
+    // tried in order, after whatever nick was originally registered with
+    pub fn candidates(candidates: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        NickFallback {
+            remaining: candidates.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    // `nick_`, `nick__`, and so on, up to `attempts` underscores
+    pub fn suffixed(nick: &str, attempts: usize) -> Self {
+        Self::candidates((1..=attempts).map(|n| format!("{nick}{}", "_".repeat(n))))
+    }
+
+    pub fn next_nick(&mut self) -> Option<String> {
+        self.remaining.pop_front()
+    }
+
+    // the NICK to send in response to ERR_NICKNAMEINUSE, or None if it's some other message or we've run out
+    pub fn handle(&mut self, msg: &Message) -> Option<Message> {
+        numerics::is_nick_in_use(msg)?;
+        let nick = self.next_nick()?;
+        Message::new(None, KnownCommand::Nick.into(), [nick], None::<&str>).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suffixed() {
+        let mut fallback = NickFallback::suffixed("bot", 2);
+        let in_use: Message = ":irc.server 433 * bot :Nickname is already in use"
+            .parse()
+            .unwrap();
+
+        let nick = fallback.handle(&in_use).unwrap();
+        assert_eq!(b"NICK bot_\r\n", &nick.to_bytes().unwrap()[..]);
+        assert_eq!(
+            Some("bot__".to_string()),
+            fallback
+                .handle(&in_use)
+                .and_then(|m| m.params().first().cloned())
+        );
+        assert!(fallback.handle(&in_use).is_none());
+    }
+
+    #[test]
+    fn candidates() {
+        let mut fallback = NickFallback::candidates(["alt", "other"]);
+        let welcome: Message = ":irc.server 001 bot :Welcome".parse().unwrap();
+        assert!(fallback.handle(&welcome).is_none());
+
+        assert_eq!(Some("alt".to_string()), fallback.next_nick());
+        assert_eq!(Some("other".to_string()), fallback.next_nick());
+        assert_eq!(None, fallback.next_nick());
+    }
+}
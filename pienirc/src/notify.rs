@@ -0,0 +1,63 @@
+use crate::{Command, KnownCommand, Message};
+
+// AWAY from away-notify, where Some(None) means the user is back and Some(Some(reason)) means they're away
+pub fn as_away(msg: &Message) -> Option<Option<String>> {
+    if msg.command().as_known() != Some(KnownCommand::Away) {
+        return None;
+    }
+
+    // an empty reason is also how some clients mark themselves back
+    Some(
+        msg.all_params()
+            .next()
+            .filter(|r| !r.is_empty())
+            .map(|r| r.to_string()),
+    )
+}
+
+// ACCOUNT from account-notify, where Some(None) means the user logged out
+pub fn as_account(msg: &Message) -> Option<Option<String>> {
+    let Command::General(command) = msg.command() else {
+        return None;
+    };
+    if !command.eq_ignore_ascii_case("ACCOUNT") {
+        return None;
+    }
+
+    let account = msg.all_params().next()?;
+    Some(Some(account).filter(|a| *a != "*").map(|a| a.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn away() {
+        let message: Message = ":nick!user@host AWAY :Gone to lunch".parse().unwrap();
+        assert_eq!(Some(Some("Gone to lunch".to_string())), as_away(&message));
+
+        let message: Message = ":nick!user@host AWAY".parse().unwrap();
+        assert_eq!(Some(None), as_away(&message));
+    }
+
+    #[test]
+    fn account() {
+        let message: Message = ":nick!user@host ACCOUNT someone".parse().unwrap();
+        assert_eq!(Some(Some("someone".to_string())), as_account(&message));
+
+        let message: Message = ":nick!user@host ACCOUNT *".parse().unwrap();
+        assert_eq!(Some(None), as_account(&message));
+
+        // no account at all isn't a valid ACCOUNT message
+        let message: Message = ":nick!user@host ACCOUNT".parse().unwrap();
+        assert_eq!(None, as_account(&message));
+    }
+
+    #[test]
+    fn other_commands() {
+        let message: Message = ":nick!user@host PRIVMSG #chan :hi".parse().unwrap();
+        assert_eq!(None, as_away(&message));
+        assert_eq!(None, as_account(&message));
+    }
+}
@@ -0,0 +1,366 @@
+use std::time::{Duration, SystemTime};
+
+use crate::{
+    isupport::PrefixMap,
+    names::{self, NamesReply},
+    Command, Message,
+};
+
+// declaring these through a macro keeps the constants and the name lookup from drifting apart
+macro_rules! numerics {
+    ($($name:ident = $value:literal,)*) => {
+        $(pub const $name: u16 = $value;)*
+
+        pub fn numeric_name(n: u16) -> Option<&'static str> {
+            match n {
+                $($value => Some(stringify!($name)),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+// RFC 2812 replies, though 005 uses the de facto RPL_ISUPPORT instead of RPL_BOUNCE.
+// widely supported extensions, like WHOX's 354, are mixed in by number.
+numerics! {
+    RPL_WELCOME = 1,
+    RPL_YOURHOST = 2,
+    RPL_CREATED = 3,
+    RPL_MYINFO = 4,
+    RPL_ISUPPORT = 5,
+
+    RPL_TRACELINK = 200,
+    RPL_TRACECONNECTING = 201,
+    RPL_TRACEHANDSHAKE = 202,
+    RPL_TRACEUNKNOWN = 203,
+    RPL_TRACEOPERATOR = 204,
+    RPL_TRACEUSER = 205,
+    RPL_TRACESERVER = 206,
+    RPL_TRACESERVICE = 207,
+    RPL_TRACENEWTYPE = 208,
+    RPL_TRACECLASS = 209,
+    RPL_TRACERECONNECT = 210,
+    RPL_STATSLINKINFO = 211,
+    RPL_STATSCOMMANDS = 212,
+    RPL_ENDOFSTATS = 219,
+    RPL_UMODEIS = 221,
+    RPL_SERVLIST = 234,
+    RPL_SERVLISTEND = 235,
+    RPL_STATSUPTIME = 242,
+    RPL_STATSOLINE = 243,
+    RPL_LUSERCLIENT = 251,
+    RPL_LUSEROP = 252,
+    RPL_LUSERUNKNOWN = 253,
+    RPL_LUSERCHANNELS = 254,
+    RPL_LUSERME = 255,
+    RPL_ADMINME = 256,
+    RPL_ADMINLOC1 = 257,
+    RPL_ADMINLOC2 = 258,
+    RPL_ADMINEMAIL = 259,
+    RPL_TRACELOG = 261,
+    RPL_TRACEEND = 262,
+    RPL_TRYAGAIN = 263,
+
+    RPL_AWAY = 301,
+    RPL_USERHOST = 302,
+    RPL_ISON = 303,
+    RPL_UNAWAY = 305,
+    RPL_NOWAWAY = 306,
+    RPL_WHOISUSER = 311,
+    RPL_WHOISSERVER = 312,
+    RPL_WHOISOPERATOR = 313,
+    RPL_WHOWASUSER = 314,
+    RPL_ENDOFWHO = 315,
+    RPL_WHOISIDLE = 317,
+    RPL_ENDOFWHOIS = 318,
+    RPL_WHOISCHANNELS = 319,
+    RPL_LISTSTART = 321,
+    RPL_LIST = 322,
+    RPL_LISTEND = 323,
+    RPL_CHANNELMODEIS = 324,
+    RPL_UNIQOPIS = 325,
+    RPL_NOTOPIC = 331,
+    RPL_TOPIC = 332,
+    RPL_TOPICWHOTIME = 333,
+    RPL_INVITING = 341,
+    RPL_SUMMONING = 342,
+    RPL_INVITELIST = 346,
+    RPL_ENDOFINVITELIST = 347,
+    RPL_EXCEPTLIST = 348,
+    RPL_ENDOFEXCEPTLIST = 349,
+    RPL_VERSION = 351,
+    RPL_WHOREPLY = 352,
+    RPL_NAMREPLY = 353,
+    RPL_WHOSPCRPL = 354,
+    RPL_LINKS = 364,
+    RPL_ENDOFLINKS = 365,
+    RPL_ENDOFNAMES = 366,
+    RPL_BANLIST = 367,
+    RPL_ENDOFBANLIST = 368,
+    RPL_ENDOFWHOWAS = 369,
+    RPL_INFO = 371,
+    RPL_MOTD = 372,
+    RPL_ENDOFINFO = 374,
+    RPL_MOTDSTART = 375,
+    RPL_ENDOFMOTD = 376,
+    RPL_YOUREOPER = 381,
+    RPL_REHASHING = 382,
+    RPL_YOURESERVICE = 383,
+    RPL_TIME = 391,
+    RPL_USERSSTART = 392,
+    RPL_USERS = 393,
+    RPL_ENDOFUSERS = 394,
+    RPL_NOUSERS = 395,
+
+    ERR_NOSUCHNICK = 401,
+    ERR_NOSUCHSERVER = 402,
+    ERR_NOSUCHCHANNEL = 403,
+    ERR_CANNOTSENDTOCHAN = 404,
+    ERR_TOOMANYCHANNELS = 405,
+    ERR_WASNOSUCHNICK = 406,
+    ERR_TOOMANYTARGETS = 407,
+    ERR_NOSUCHSERVICE = 408,
+    ERR_NOORIGIN = 409,
+    ERR_NORECIPIENT = 411,
+    ERR_NOTEXTTOSEND = 412,
+    ERR_NOTOPLEVEL = 413,
+    ERR_WILDTOPLEVEL = 414,
+    ERR_BADMASK = 415,
+    ERR_UNKNOWNCOMMAND = 421,
+    ERR_NOMOTD = 422,
+    ERR_NOADMININFO = 423,
+    ERR_FILEERROR = 424,
+    ERR_NONICKNAMEGIVEN = 431,
+    ERR_ERRONEUSNICKNAME = 432,
+    ERR_NICKNAMEINUSE = 433,
+    ERR_NICKCOLLISION = 436,
+    ERR_UNAVAILRESOURCE = 437,
+    ERR_USERNOTINCHANNEL = 441,
+    ERR_NOTONCHANNEL = 442,
+    ERR_USERONCHANNEL = 443,
+    ERR_NOLOGIN = 444,
+    ERR_SUMMONDISABLED = 445,
+    ERR_USERSDISABLED = 446,
+    ERR_NOTREGISTERED = 451,
+    ERR_NEEDMOREPARAMS = 461,
+    ERR_ALREADYREGISTRED = 462,
+    ERR_NOPERMFORHOST = 463,
+    ERR_PASSWDMISMATCH = 464,
+    ERR_YOUREBANNEDCREEP = 465,
+    ERR_YOUWILLBEBANNED = 466,
+    ERR_KEYSET = 467,
+    ERR_CHANNELISFULL = 471,
+    ERR_UNKNOWNMODE = 472,
+    ERR_INVITEONLYCHAN = 473,
+    ERR_BANNEDFROMCHAN = 474,
+    ERR_BADCHANNELKEY = 475,
+    ERR_BADCHANMASK = 476,
+    ERR_NOCHANMODES = 477,
+    ERR_BANLISTFULL = 478,
+    ERR_NOPRIVILEGES = 481,
+    ERR_CHANOPRIVSNEEDED = 482,
+    ERR_CANTKILLSERVER = 483,
+    ERR_RESTRICTED = 484,
+    ERR_UNIQOPPRIVSNEEDED = 485,
+    ERR_NOOPERHOST = 491,
+
+    ERR_UMODEUNKNOWNFLAG = 501,
+    ERR_USERSDONTMATCH = 502,
+
+    RPL_MONONLINE = 730,
+    RPL_MONOFFLINE = 731,
+    RPL_MONLIST = 732,
+    RPL_ENDOFMONLIST = 733,
+    ERR_MONLISTFULL = 734,
+}
+
+// the numerics with well known parameters, pulled out into fields.
+// the first parameter of every numeric, our own nick, is left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KnownNumeric {
+    Welcome { text: String },
+    Topic { channel: String, topic: String },
+    // uses the default prefixes, so parse_353 is the way to go when the server advertises its own
+    Names(NamesReply),
+    EndOfNames { channel: String },
+    NicknameInUse { nick: String },
+    // unrecognized or malformed numerics, with all of their parameters, including the trailing one
+    Other { numeric: u16, params: Vec<String> },
+}
+
+// None if the message isn't a numeric at all
+pub fn classify(msg: &Message) -> Option<KnownNumeric> {
+    let Command::Numeric(numeric) = *msg.command() else {
+        return None;
+    };
+
+    let params = msg.all_params().collect::<Vec<_>>();
+    let string = |s: &str| s.to_string();
+
+    let known = match (numeric, &params[..]) {
+        (RPL_WELCOME, [_, text]) => Some(KnownNumeric::Welcome { text: string(text) }),
+        (RPL_TOPIC, [_, channel, topic]) => Some(KnownNumeric::Topic {
+            channel: string(channel),
+            topic: string(topic),
+        }),
+        (RPL_NAMREPLY, _) => names::parse_353(msg, &PrefixMap::default()).map(KnownNumeric::Names),
+        (RPL_ENDOFNAMES, [_, channel, ..]) => Some(KnownNumeric::EndOfNames {
+            channel: string(channel),
+        }),
+        (ERR_NICKNAMEINUSE, [_, nick, ..]) => {
+            Some(KnownNumeric::NicknameInUse { nick: string(nick) })
+        }
+        _ => None,
+    };
+
+    Some(known.unwrap_or_else(|| KnownNumeric::Other {
+        numeric,
+        params: params.into_iter().map(string).collect(),
+    }))
+}
+
+// the nick that was rejected, like `bot` in `:server 433 * bot :Nickname is already in use`
+pub fn is_nick_in_use(msg: &Message) -> Option<&str> {
+    if msg.command() != &Command::Numeric(ERR_NICKNAMEINUSE) {
+        return None;
+    }
+
+    // the first parameter is our nick, which is `*` if we haven't registered yet
+    msg.params().get(1).map(|n| n.as_str())
+}
+
+// RPL_TOPIC, like `:server 332 me #chan :the topic`, as the channel and topic
+pub fn parse_332(msg: &Message) -> Option<(String, String)> {
+    if msg.command() != &Command::Numeric(RPL_TOPIC) {
+        return None;
+    }
+
+    let [_, channel, topic] = msg.all_params().collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    Some((channel.to_string(), topic.to_string()))
+}
+
+// RPL_TOPICWHOTIME, like `:server 333 me #chan nick!user@host 1700000000`, as the channel, who set the topic, and when.
+// the setter is sometimes just a nick.
+pub fn parse_333(msg: &Message) -> Option<(String, String, SystemTime)> {
+    if msg.command() != &Command::Numeric(RPL_TOPICWHOTIME) {
+        return None;
+    }
+
+    let [_, channel, setter, time] = msg.all_params().collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    let seconds = time.parse().ok()?;
+
+    Some((
+        channel.to_string(),
+        setter.to_string(),
+        SystemTime::UNIX_EPOCH + Duration::from_secs(seconds),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_lookup() {
+        assert_eq!(Some("ERR_NICKNAMEINUSE"), numeric_name(433));
+        assert_eq!(Some("RPL_WELCOME"), numeric_name(RPL_WELCOME));
+        assert_eq!(None, numeric_name(999));
+    }
+
+    #[test]
+    fn classify_topic() {
+        let message: Message = ":irc.server 332 me #chan :Welcome to the channel"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            Some(KnownNumeric::Topic {
+                channel: "#chan".to_string(),
+                topic: "Welcome to the channel".to_string(),
+            }),
+            classify(&message)
+        );
+    }
+
+    #[test]
+    fn topic_numerics() {
+        let message: Message = ":irc.server 332 me #chan :Welcome to the channel"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            Some(("#chan".to_string(), "Welcome to the channel".to_string())),
+            parse_332(&message)
+        );
+        assert_eq!(None, parse_333(&message));
+
+        // a single-word topic can come without a colon
+        let message: Message = ":irc.server 332 me #chan hi".parse().unwrap();
+        assert_eq!(
+            Some(("#chan".to_string(), "hi".to_string())),
+            parse_332(&message)
+        );
+
+        let message: Message = ":irc.server 333 me #chan nick!user@host 1700000000"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            Some((
+                "#chan".to_string(),
+                "nick!user@host".to_string(),
+                SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+            )),
+            parse_333(&message)
+        );
+
+        let message: Message = ":irc.server 333 me #chan nick :soon".parse().unwrap();
+        assert_eq!(None, parse_333(&message));
+    }
+
+    #[test]
+    fn classify_nick_in_use() {
+        let message: Message = ":irc.server 433 * bot :Nickname is already in use"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            Some(KnownNumeric::NicknameInUse {
+                nick: "bot".to_string()
+            }),
+            classify(&message)
+        );
+    }
+
+    #[test]
+    fn classify_other() {
+        let message: Message = ":irc.server 999 me foo :bar baz".parse().unwrap();
+        assert_eq!(
+            Some(KnownNumeric::Other {
+                numeric: 999,
+                params: vec!["me".to_string(), "foo".to_string(), "bar baz".to_string()],
+            }),
+            classify(&message)
+        );
+
+        // missing the topic
+        let message: Message = ":irc.server 332 me #chan".parse().unwrap();
+        assert!(matches!(
+            classify(&message),
+            Some(KnownNumeric::Other { numeric: 332, .. })
+        ));
+
+        assert_eq!(None, classify(&"PING :abc".parse().unwrap()));
+    }
+
+    #[test]
+    fn nick_in_use() {
+        let message: Message = ":irc.server 433 * bot :Nickname is already in use"
+            .parse()
+            .unwrap();
+        assert_eq!(Some("bot"), is_nick_in_use(&message));
+
+        let message: Message = ":irc.server 432 * b@t :Erroneous nickname".parse().unwrap();
+        assert_eq!(None, is_nick_in_use(&message));
+    }
+}
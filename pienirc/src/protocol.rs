@@ -1,31 +1,64 @@
+use alloc::{
+    borrow::Cow,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    fmt::{self, Display},
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+#[cfg(feature = "std")]
 use std::{
     future::Future,
-    io::{self, Write},
-    sync::LazyLock,
+    io,
+    time::{Duration, SystemTime},
 };
 
+use bytes::{Buf, BytesMut};
+#[cfg(feature = "regex-parser")]
 use regex::bytes::{Captures, Regex};
+#[cfg(feature = "regex-parser")]
+use std::sync::LazyLock;
 
-pub type Result<T> = std::result::Result<T, Error>;
+use crate::casemap::CaseMapping;
+
+pub type Result<T> = core::result::Result<T, Error>;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
-    #[error("Failed to parse raw IRC message.")]
-    Parsing,
+    // the offending line, without the line terminator, along with which part of it was bad and where in the line
+    #[error(
+        "Failed to parse raw IRC message at {component:?} (byte {offset}): {:?}.",
+        String::from_utf8_lossy(.raw)
+    )]
+    Parsing {
+        raw: Vec<u8>,
+        component: Component,
+        offset: usize,
+    },
 
-    #[error("The message, including the crlf, is more than 512 bytes.")]
+    #[error("The message, including the crlf, is longer than the limit (512 bytes by default).")]
     MessageTooLong,
 
+    #[error("The parsed message, including the crlf, is longer than the limit (512 bytes by default). Skip {skip} bytes to discard it.")]
+    ParsedMessageTooLong { skip: usize },
+
+    // only writing to io can fail, which needs std
+    #[cfg(feature = "std")]
     #[error("Failed to serialize message: `{reason}`.")]
     Serialization {
         reason: &'static str,
         #[source]
-        io_error: std::io::Error,
+        io_error: io::Error,
     },
 
     #[error("Prefix has spaces or crlf.")]
     PrefixValidation,
 
+    // also covers bad middle parameters
     #[error("Command has spaces, crlf, or starts with a colon.")]
     CommandValidation,
 
@@ -34,19 +67,129 @@ pub enum Error {
 
     #[error("Last parameter has crlf")]
     LastParameterValidation,
+
+    #[error("Tag key is empty or has spaces, crlf, semicolons, or equals signs.")]
+    TagValidation,
+
+    #[error("No command provided.")]
+    MissingCommand,
+
+    #[error("No channels provided, or a channel name is invalid.")]
+    ChannelValidation,
+
+    #[error("Nickname is invalid.")]
+    NickValidation,
+
+    // the offending line, without the line terminator
+    #[error("IRC message is not valid UTF-8: {:?}.", String::from_utf8_lossy(.raw))]
+    InvalidUtf8 { raw: Vec<u8> },
+}
+
+// where in a line parsing failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+    Tags,
+    Prefix,
+    Command,
+    Parameters,
+    LastParameter,
+    // for problems with the line as a whole, like invalid utf-8, where the offset is the first bad byte
+    Line,
 }
 
+#[cfg(feature = "std")]
 pub trait Transport {
-    fn send(&mut self, message: Message) -> impl Future<Output = io::Result<()>> + Send;
-    fn receive(&mut self) -> impl Future<Output = io::Result<Option<Message>>> + Send;
+    // able to hold either kind of failure, so that callers can tell a bad connection apart from a bad message.
+    // a line that fails to parse gets dropped before its error is returned, so receiving again moves on to the next one.
+    type Error: From<io::Error> + From<Error> + fmt::Debug + std::error::Error + Send;
+
+    // may only buffer the message, where flush is what makes sure it's actually sent
+    fn send(
+        &mut self,
+        message: Message,
+    ) -> impl Future<Output = core::result::Result<(), Self::Error>> + Send;
+    fn receive(
+        &mut self,
+    ) -> impl Future<Output = core::result::Result<Option<Message>, Self::Error>> + Send;
+
+    fn flush(&mut self) -> impl Future<Output = core::result::Result<(), Self::Error>> + Send {
+        async { Ok(()) }
+    }
+}
+
+// the tag portion is limited separately from the rest of the line, per the message-tags spec
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    // the rest of the line, including the crlf
+    pub max_line: usize,
+    // the tag portion, including the leading @ and trailing space
+    pub max_tags: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_line: 512,
+            max_tags: 4096,
+        }
+    }
+}
+
+// what a message takes up on the wire, split the same way as Limits, since servers enforce them separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WireLen {
+    // the tag portion, including the leading @ and trailing space
+    pub tags: usize,
+    // the rest of the line, including the crlf
+    pub line: usize,
+}
+
+impl WireLen {
+    pub fn total(&self) -> usize {
+        self.tags + self.line
+    }
+
+    pub fn within(&self, limits: Limits) -> bool {
+        self.tags <= limits.max_tags && self.line <= limits.max_line
+    }
 }
 
-#[derive(Debug)]
+// note that deserializing skips the validation that constructing a message normally does
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Message {
-    prefix: Option<Prefix>,
-    command: Command,
-    parameters: Option<Vec<String>>,
-    last_parameter: Option<String>,
+    pub(crate) tags: Option<Vec<Tag>>,
+    pub(crate) prefix: Option<Prefix>,
+    pub(crate) command: Command,
+    pub(crate) parameters: Option<Vec<String>>,
+    pub(crate) last_parameter: Option<String>,
+    // whether the last parameter was parsed with a colon, which only matters after 14 middle parameters.
+    // None always writes one.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) last_parameter_colon: Option<bool>,
+}
+
+// the colon is just a detail of how the message was written, so two messages differing only by it are the same
+impl PartialEq for Message {
+    fn eq(&self, other: &Self) -> bool {
+        self.tags == other.tags
+            && self.prefix == other.prefix
+            && self.command == other.command
+            && self.parameters == other.parameters
+            && self.last_parameter == other.last_parameter
+    }
+}
+
+impl Eq for Message {}
+
+impl Hash for Message {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.tags.hash(state);
+        self.prefix.hash(state);
+        self.command.hash(state);
+        self.parameters.hash(state);
+        self.last_parameter.hash(state);
+    }
 }
 
 impl Message {
@@ -57,19 +200,49 @@ impl Message {
         last_parameter: Option<String>,
     ) -> Message {
         Message {
+            tags: None,
             prefix,
             command,
             parameters,
             last_parameter,
+            last_parameter_colon: None,
         }
     }
 
+    // takes anything string-like, so that `&str`s work as-is, though a missing last parameter then needs
+    // its type spelled out, like `None::<&str>`. the same goes for having no middle parameters.
     pub fn new(
         prefix: Option<Prefix>,
         command: Command,
-        parameters: Option<Vec<String>>,
-        last_parameter: Option<String>,
+        parameters: impl IntoIterator<Item = impl Into<String>>,
+        last_parameter: Option<impl Into<String>>,
+    ) -> Result<Message> {
+        Self::new_with_limits(
+            prefix,
+            command,
+            parameters,
+            last_parameter,
+            Limits::default(),
+        )
+    }
+
+    // for servers that advertise limits other than the rfc ones
+    pub fn new_with_limits(
+        prefix: Option<Prefix>,
+        command: Command,
+        parameters: impl IntoIterator<Item = impl Into<String>>,
+        last_parameter: Option<impl Into<String>>,
+        limits: Limits,
     ) -> Result<Message> {
+        let parameters = Some(
+            parameters
+                .into_iter()
+                .map(Into::into)
+                .collect::<Vec<String>>(),
+        )
+        .filter(|p| !p.is_empty());
+        let last_parameter = last_parameter.map(Into::into);
+
         fn sp(s: &str) -> bool {
             // it may be more correct to check for "\r\n", but, since it's invalid anyway to have those chars,
             // might as well do it this way
@@ -82,17 +255,28 @@ impl Message {
                 ref nickname,
                 ref user,
                 ref server,
-            })) if sp(nickname) || sp(user) || sp(server) => Err(Error::PrefixValidation),
+            })) if sp(nickname)
+                || user.as_deref().is_some_and(sp)
+                || server.as_deref().is_some_and(sp) =>
+            {
+                Err(Error::PrefixValidation)
+            }
             _ => Ok(()),
         }?;
 
         match command {
-            Command::General(ref c) if sp(c) => Err(Error::PrefixValidation),
+            // anything else, including an empty command, would be garbage on the wire
+            Command::General(ref c)
+                if !(c.bytes().all(|b| b.is_ascii_alphabetic()) && !c.is_empty()
+                    || c.len() == 3 && c.bytes().all(|b| b.is_ascii_digit())) =>
+            {
+                Err(Error::CommandValidation)
+            }
             _ => Ok(()),
         }?;
 
         match parameters {
-            Some(ref p) if p.iter().any(|p| sp(p) || p.starts_with(':')) => {
+            Some(ref p) if p.iter().any(|p| Self::invalid_middle_parameter(p)) => {
                 Err(Error::CommandValidation)
             }
             // when it comes to parsing, excess parameters get treated as last_parameter
@@ -102,20 +286,327 @@ impl Message {
         }?;
 
         match last_parameter {
-            Some(ref s) if s.contains("\r\n") => Err(Error::LastParameterValidation),
+            // a lone cr or lf would still end the line for plenty of servers
+            Some(ref s) if s.contains(['\r', '\n']) => Err(Error::LastParameterValidation),
             _ => Ok(()),
         }?;
 
-        if Self::calc_len(&prefix, &command, &parameters, &last_parameter) > 512 {
+        if Self::calc_len(&None, &prefix, &command, &parameters, &last_parameter, None)
+            > limits.max_line
+        {
             Err(Error::MessageTooLong)
         } else {
             Ok(Message {
+                tags: None,
                 prefix,
                 command,
                 parameters,
                 last_parameter,
+                last_parameter_colon: None,
+            })
+        }
+    }
+
+    // only the last parameter can be empty, since an empty middle one would just disappear when parsed
+    fn invalid_middle_parameter(p: &str) -> bool {
+        p.is_empty() || p.contains([' ', '\r', '\n']) || p.starts_with(':')
+    }
+
+    pub fn builder() -> MessageBuilder {
+        MessageBuilder::default()
+    }
+
+    // splits text that wouldn't fit into a single message, preferring to split on whitespace
+    pub fn split_privmsg(target: &str, text: &str) -> Result<Vec<Message>> {
+        Self::split_text(KnownCommand::Privmsg, target, text)
+    }
+
+    pub fn split_notice(target: &str, text: &str) -> Result<Vec<Message>> {
+        Self::split_text(KnownCommand::Notice, target, text)
+    }
+
+    // like `JOIN #a,#b key1,key2`. keyed channels go first, so that the unkeyed ones don't need empty key slots.
+    // an empty key is the same as no key.
+    pub fn join(channels: &[(&str, Option<&str>)]) -> Result<Message> {
+        if channels.is_empty()
+            || channels.iter().any(|(c, _)| {
+                !crate::validate::is_valid_channel(c, crate::validate::DEFAULT_CHANTYPES)
             })
+        {
+            return Err(Error::ChannelValidation);
+        }
+        // a comma would throw off which key goes with which channel
+        if channels
+            .iter()
+            .any(|(_, k)| k.is_some_and(|k| k.contains(',')))
+        {
+            return Err(Error::CommandValidation);
+        }
+
+        let (keyed, unkeyed): (Vec<_>, Vec<_>) = channels
+            .iter()
+            .partition(|(_, k)| k.is_some_and(|k| !k.is_empty()));
+        let names = keyed
+            .iter()
+            .chain(&unkeyed)
+            .map(|(c, _)| *c)
+            .collect::<Vec<_>>();
+        let keys = keyed.iter().filter_map(|(_, k)| *k).collect::<Vec<_>>();
+
+        let builder = Message::builder()
+            .command(KnownCommand::Join.into())
+            .param(names.join(","));
+        match keys.is_empty() {
+            true => builder,
+            false => builder.param(keys.join(",")),
+        }
+        .build()
+    }
+
+    // an empty topic clears it
+    pub fn topic(channel: &str, text: &str) -> Result<Message> {
+        if !crate::validate::is_valid_channel(channel, crate::validate::DEFAULT_CHANTYPES) {
+            return Err(Error::ChannelValidation);
+        }
+
+        Message::builder()
+            .command(KnownCommand::Topic.into())
+            .param(channel)
+            .trailing(text)
+            .build()
+    }
+
+    pub fn kick(channel: &str, nick: &str, reason: Option<&str>) -> Result<Message> {
+        if !crate::validate::is_valid_channel(channel, crate::validate::DEFAULT_CHANTYPES) {
+            return Err(Error::ChannelValidation);
+        }
+        // the server's NICKLEN doesn't matter for someone already on the channel
+        if !crate::validate::is_valid_nick_len(nick, usize::MAX) {
+            return Err(Error::NickValidation);
+        }
+
+        let builder = Message::builder()
+            .command(KnownCommand::Kick.into())
+            .param(channel)
+            .param(nick);
+        match reason {
+            Some(reason) => builder.trailing(reason),
+            None => builder,
+        }
+        .build()
+    }
+
+    // like `PART #a,#b :reason`
+    pub fn part(channels: &[&str], reason: Option<&str>) -> Result<Message> {
+        if channels.is_empty()
+            || channels
+                .iter()
+                .any(|c| !crate::validate::is_valid_channel(c, crate::validate::DEFAULT_CHANTYPES))
+        {
+            return Err(Error::ChannelValidation);
+        }
+
+        let builder = Message::builder()
+            .command(KnownCommand::Part.into())
+            .param(channels.join(","));
+        match reason {
+            Some(reason) => builder.trailing(reason),
+            None => builder,
+        }
+        .build()
+    }
+
+    // the messages to send when first connecting, in the order servers expect them: PASS, NICK, then USER
+    pub fn register(
+        nick: &str,
+        username: &str,
+        realname: &str,
+        password: Option<&str>,
+    ) -> Result<Vec<Message>> {
+        // we don't know the server's NICKLEN until after registering, so only the characters get checked
+        if !crate::validate::is_valid_nick_len(nick, usize::MAX) {
+            return Err(Error::NickValidation);
+        }
+
+        let mut messages = Vec::with_capacity(3);
+        if let Some(password) = password {
+            messages.push(Message::new(
+                None,
+                KnownCommand::Pass.into(),
+                None::<&str>,
+                Some(password),
+            )?);
+        }
+        messages.push(Message::new(
+            None,
+            KnownCommand::Nick.into(),
+            [nick],
+            None::<&str>,
+        )?);
+        // the mode and unused parameters are what the modern spec suggests
+        messages.push(Message::new(
+            None,
+            KnownCommand::User.into(),
+            [username, "0", "*"],
+            Some(realname),
+        )?);
+        Ok(messages)
+    }
+
+    fn split_text(command: KnownCommand, target: &str, text: &str) -> Result<Vec<Message>> {
+        let command: Command = command.into();
+        let overhead = Self::calc_len(
+            &None,
+            &None,
+            &command,
+            &Some(vec![target.to_string()]),
+            &Some(String::new()),
+            None,
+        );
+        // we need room for at least one of any codepoint, else we'd never make progress
+        let Some(available) = 512usize.checked_sub(overhead).filter(|a| *a >= 4) else {
+            return Err(Error::MessageTooLong);
+        };
+
+        let mut messages = Vec::new();
+        let mut rest = text;
+        while !rest.is_empty() {
+            let (chunk, next) = if rest.len() <= available {
+                (rest, "")
+            } else {
+                // never split in the middle of a codepoint
+                let mut end = available;
+                while !rest.is_char_boundary(end) {
+                    end -= 1;
+                }
+
+                let (head, tail) = rest.split_at(end);
+                match tail.strip_prefix(|c: char| c.is_ascii_whitespace()) {
+                    // the boundary happens to already be on whitespace
+                    Some(tail) => (head, tail),
+                    None => match head.rfind(|c: char| c.is_ascii_whitespace()) {
+                        Some(ws) if ws > 0 => (&rest[..ws], &rest[ws + 1..]),
+                        _ => (head, tail),
+                    },
+                }
+            };
+
+            messages.push(Message::new(None, command.clone(), [target], Some(chunk))?);
+            rest = next;
         }
+
+        Ok(messages)
+    }
+
+    // if this is a PING, the PONG to reply with.
+    // some servers send the token as a middle parameter instead of a trailing one, so it goes back the same way.
+    pub fn pong_reply(&self) -> Option<Message> {
+        if self.command.as_known() != Some(KnownCommand::Ping) {
+            return None;
+        }
+
+        let (parameters, last_parameter) = match (&self.last_parameter, self.params().first()) {
+            (Some(l), _) => (None, Some(l.clone())),
+            (None, Some(p)) => (Some(vec![p.clone()]), None),
+            (None, None) => (None, None),
+        };
+        Some(Message::new_unchecked(
+            None,
+            KnownCommand::Pong.into(),
+            parameters,
+            last_parameter,
+        ))
+    }
+
+    pub fn with_tags(self, tags: Option<Vec<Tag>>) -> Result<Message> {
+        if let Some(ref t) = tags {
+            // values get escaped, so only keys need checking
+            if t.iter()
+                .any(|t| t.key.is_empty() || t.key.contains([' ', '\r', '\n', ';', '=']))
+            {
+                return Err(Error::TagValidation);
+            }
+        }
+
+        let message = Message { tags, ..self };
+        if message.exceeds_limits(Limits::default()) {
+            Err(Error::MessageTooLong)
+        } else {
+            Ok(message)
+        }
+    }
+
+    // everything else stays the same, like for rewriting the text of a relayed PRIVMSG
+    pub fn with_trailing(self, text: impl Into<String>) -> Result<Message> {
+        let text = text.into();
+        // the same check as new
+        if text.contains(['\r', '\n']) {
+            return Err(Error::LastParameterValidation);
+        }
+
+        // the old colon doesn't say anything about the new text
+        let message = Message {
+            last_parameter: Some(text),
+            last_parameter_colon: None,
+            ..self
+        };
+        if message.exceeds_limits(Limits::default()) {
+            Err(Error::MessageTooLong)
+        } else {
+            Ok(message)
+        }
+    }
+
+    // after exactly 14 middle parameters, the last parameter's colon can be left off to save a byte,
+    // as long as it can't be mistaken for something else. otherwise, nothing changes.
+    pub fn omit_trailing_colon(mut self) -> Message {
+        let fourteen = self.parameters.as_ref().is_some_and(|p| p.len() == 14);
+        if fourteen
+            && self
+                .last_parameter
+                .as_ref()
+                .is_some_and(|l| !l.is_empty() && !l.contains(' ') && !l.starts_with(':'))
+        {
+            self.last_parameter_colon = Some(false);
+        }
+        self
+    }
+
+    // without a trailing parameter, the message is left alone, since there's no telling whether
+    // the last middle parameter is text or something like the channel of `JOIN #chan`
+    pub fn map_trailing(mut self, f: impl FnOnce(String) -> String) -> Result<Message> {
+        match self.last_parameter.take() {
+            Some(text) => self.with_trailing(f(text)),
+            None => Ok(self),
+        }
+    }
+
+    // tags aren't part of this, so use `with_tags` to put them back on
+    pub fn into_parts(self) -> (Option<Prefix>, Command, Option<Vec<String>>, Option<String>) {
+        (
+            self.prefix,
+            self.command,
+            self.parameters,
+            self.last_parameter,
+        )
+    }
+
+    pub fn from_parts(
+        prefix: Option<Prefix>,
+        command: Command,
+        parameters: Option<Vec<String>>,
+        last_parameter: Option<String>,
+    ) -> Result<Message> {
+        Self::new(
+            prefix,
+            command,
+            parameters.into_iter().flatten(),
+            last_parameter,
+        )
+    }
+
+    pub fn tags(&self) -> &Option<Vec<Tag>> {
+        &self.tags
     }
 
     pub fn prefix(&self) -> &Option<Prefix> {
@@ -134,86 +625,342 @@ impl Message {
         &self.last_parameter
     }
 
+    // easier to work with than the above, for when the distinction between None and empty doesn't matter
+    pub fn params(&self) -> &[String] {
+        self.parameters.as_deref().unwrap_or_default()
+    }
+
+    pub fn trailing(&self) -> Option<&str> {
+        self.last_parameter.as_deref()
+    }
+
+    // the middle parameters followed by the trailing one, if any.
+    // senders are free to leave the colon off of the last parameter, or to add one when it's not needed,
+    // so this is the way to get at parameters by position without caring which way they were sent.
+    pub fn all_params(&self) -> impl Iterator<Item = &str> {
+        self.params()
+            .iter()
+            .map(|p| p.as_str())
+            .chain(self.trailing())
+    }
+
+    pub fn prefix_ref(&self) -> Option<&Prefix> {
+        self.prefix.as_ref()
+    }
+
+    // whether the last parameter was parsed with a colon, so None for messages that weren't parsed,
+    // unless omit_trailing_colon left it off. it can only be without one after 14 middle parameters.
+    pub fn last_parameter_had_colon(&self) -> Option<bool> {
+        self.last_parameter_colon
+    }
+
+    // None for server prefixes, as well as when there's no prefix
+    pub fn source_nick(&self) -> Option<&str> {
+        self.prefix.as_ref()?.nick()
+    }
+
+    // the target and text, like `("#chan", "hi")` for `PRIVMSG #chan :hi`
+    pub fn as_privmsg(&self) -> Option<(&str, &str)> {
+        self.target_and_text(KnownCommand::Privmsg)
+    }
+
+    pub fn as_notice(&self) -> Option<(&str, &str)> {
+        self.target_and_text(KnownCommand::Notice)
+    }
+
+    // where a reply to a PRIVMSG should go: the channel it was sent to, or the sender if it was sent to us directly
+    pub fn reply_target(&self, my_nick: &str, chantypes: &str) -> Option<&str> {
+        let (target, _) = self.as_privmsg()?;
+        if crate::validate::is_channel_target(target, chantypes) {
+            Some(target)
+        } else if CaseMapping::default().eq(target, my_nick) {
+            self.source_nick()
+        } else {
+            None
+        }
+    }
+
+    fn target_and_text(&self, command: KnownCommand) -> Option<(&str, &str)> {
+        if self.command.as_known() != Some(command) {
+            return None;
+        }
+
+        match self.all_params().collect::<Vec<_>>()[..] {
+            [target, text] => Some((target, text)),
+            _ => None,
+        }
+    }
+
+    // the comma-separated targets in the first parameter, like in `PRIVMSG #a,#b :hi`
+    pub fn targets(&self) -> Vec<&str> {
+        self.parameters
+            .as_ref()
+            .and_then(|p| p.first())
+            .map_or_else(Vec::new, |p| {
+                p.split(',').filter(|t| !t.is_empty()).collect()
+            })
+    }
+
+    // the comma-separated keys of a JOIN, which line up with the targets.
+    // since they're positional, empty keys are kept.
+    pub fn keys(&self) -> Vec<&str> {
+        if self.command.as_known() != Some(KnownCommand::Join) {
+            return Vec::new();
+        }
+
+        self.parameters
+            .as_ref()
+            .and_then(|p| p.get(1))
+            .map_or_else(Vec::new, |p| p.split(',').collect())
+    }
+
+    // from the `time` tag that the server-time cap adds, like `2023-06-01T12:34:56.789Z`
+    #[cfg(feature = "std")]
+    pub fn server_time(&self) -> Option<SystemTime> {
+        let time = self
+            .tags
+            .as_ref()?
+            .iter()
+            .find(|t| t.key == "time")?
+            .value
+            .as_deref()?;
+
+        parse_timestamp(time).map(|since_epoch| SystemTime::UNIX_EPOCH + since_epoch)
+    }
+
+    #[cfg(feature = "chrono")]
+    pub fn server_time_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.server_time().map(Into::into)
+    }
+
     pub fn to_bytes(&self) -> Result<Vec<u8>> {
-        let len = Self::calc_len(
-            &self.prefix,
-            &self.command,
-            &self.parameters,
-            &self.last_parameter,
-        );
-        if len > 512 {
+        let mut b = Vec::with_capacity(self.wire_len().total());
+        self.to_bytes_into(&mut b)?;
+        Ok(b)
+    }
+
+    pub fn to_bytes_with_limits(&self, limits: Limits) -> Result<Vec<u8>> {
+        let mut b = Vec::with_capacity(self.wire_len().total());
+        self.to_bytes_into_with_limits(&mut b, limits)?;
+        Ok(b)
+    }
+
+    // appends to the buffer, so that callers can reuse a single buffer across many messages
+    pub fn to_bytes_into(&self, b: &mut Vec<u8>) -> Result<()> {
+        self.to_bytes_into_with_limits(b, Limits::default())
+    }
+
+    pub fn to_bytes_into_with_limits(&self, b: &mut Vec<u8>, limits: Limits) -> Result<()> {
+        if self.exceeds_limits(limits) {
+            return Err(Error::MessageTooLong);
+        }
+
+        b.reserve(self.wire_len().total());
+        self.write_unchecked(b);
+        Ok(())
+    }
+
+    // writes each piece straight to the writer, rather than building up the whole line first
+    #[cfg(feature = "std")]
+    pub fn write_to<W: io::Write>(&self, w: &mut W) -> Result<()> {
+        if self.exceeds_limits(Limits::default()) {
             return Err(Error::MessageTooLong);
         }
 
-        let mut b = Vec::with_capacity(len);
+        self.write_segments(|s| w.write_all(&s))
+            .map_err(|e| Error::Serialization {
+                reason: "Unable to write message.",
+                io_error: e,
+            })
+    }
+
+    // the same pieces write_to would write, for writers that can't be passed in, like async ones.
+    // besides a numeric command, they're all borrowed from the message.
+    pub fn segments(&self) -> Result<Vec<Cow<'_, [u8]>>> {
+        if self.exceeds_limits(Limits::default()) {
+            return Err(Error::MessageTooLong);
+        }
+
+        let mut segments = Vec::new();
+        let _ = self.write_segments(|s| {
+            segments.push(s);
+            Ok::<_, core::convert::Infallible>(())
+        });
+        Ok(segments)
+    }
+
+    // skips the length check, which Display doesn't care about
+    fn write_unchecked(&self, b: &mut Vec<u8>) {
+        let _ = self.write_segments(|s| {
+            b.extend_from_slice(&s);
+            Ok::<_, core::convert::Infallible>(())
+        });
+    }
+
+    // the one place the wire format gets written, which every kind of writer goes through
+    fn write_segments<'a, E>(
+        &'a self,
+        mut w: impl FnMut(Cow<'a, [u8]>) -> core::result::Result<(), E>,
+    ) -> core::result::Result<(), E> {
+        if let Some(t) = &self.tags {
+            w(b"@".into())?;
+            for (i, t) in t.iter().enumerate() {
+                if i > 0 {
+                    w(b";".into())?;
+                }
+                w(t.key.as_bytes().into())?;
+                if let Some(v) = &t.value {
+                    w(b"=".into())?;
+                    escape_tag_value(v, &mut w)?;
+                }
+            }
+            w(b" ".into())?;
+        }
 
         match &self.prefix {
-            Some(Prefix::Server(s)) => write!(b, ":{} ", s),
+            Some(Prefix::Server(s)) => {
+                w(b":".into())?;
+                w(s.as_bytes().into())?;
+                w(b" ".into())?;
+            }
             Some(Prefix::User(UserMask {
                 nickname,
                 user,
                 server,
-            })) => write!(b, ":{}!{}@{}", nickname, user, server),
-            _ => Ok(()),
+            })) => {
+                // only the components that are present, like `:nick` or `:nick@host`
+                w(b":".into())?;
+                w(nickname.as_bytes().into())?;
+                if let Some(u) = user {
+                    w(b"!".into())?;
+                    w(u.as_bytes().into())?;
+                }
+                if let Some(s) = server {
+                    w(b"@".into())?;
+                    w(s.as_bytes().into())?;
+                }
+                w(b" ".into())?;
+            }
+            None => (),
         }
-        .map_err(|e| Error::Serialization {
-            reason: "Unable to write prefix.",
-            io_error: e,
-        })?;
 
         match &self.command {
-            Command::Numeric(n) => write!(b, "{:03}", n),
-            Command::General(c) => b.write_all(c.as_bytes()),
+            // zero-padded to three digits, like `001`
+            Command::Numeric(n) => w(format!("{:03}", n).into_bytes().into())?,
+            Command::General(c) => w(c.as_bytes().into())?,
         }
-        .map_err(|e| Error::Serialization {
-            reason: "Unable to write command.",
-            io_error: e,
-        })?;
 
-        if let Some(p) = &self.parameters {
-            for p in p.iter() {
-                write!(b, " {}", p).map_err(|e| Error::Serialization {
-                    reason: "Unable to write parameters.",
-                    io_error: e,
-                })?;
-            }
-        };
+        for p in self.params() {
+            w(b" ".into())?;
+            w(p.as_bytes().into())?;
+        }
 
         if let Some(p) = &self.last_parameter {
-            write!(b, " :{}", p).map_err(|e| Error::Serialization {
-                reason: "Unable to write last parameter.",
-                io_error: e,
-            })?;
+            w(b" ".into())?;
+            if !Self::omits_colon(
+                &self.parameters,
+                &self.last_parameter,
+                self.last_parameter_colon,
+            ) {
+                w(b":".into())?;
+            }
+            w(p.as_bytes().into())?;
         }
 
-        b.write_all(b"\r\n").map_err(|e| Error::Serialization {
-            reason: "Unable to write crlf.",
-            io_error: e,
-        })?;
-
-        Ok(b)
+        w(b"\r\n".into())
     }
 
     pub fn parse(input: &[u8]) -> Result<Option<(Message, usize)>> {
-        let Some(size) = input.windows(2).position(|w| w == b"\r\n") else {
-            // if we don't have a complete line, there's simply incomplete data in the buffer
-            // which is not an error
+        Self::parse_with(input, false, Limits::default())
+    }
+
+    // also accepts lines terminated by a bare lf, which some older bouncers and test harnesses send
+    pub fn parse_lenient(input: &[u8]) -> Result<Option<(Message, usize)>> {
+        Self::parse_with(input, true, Limits::default())
+    }
+
+    pub fn parse_with_limits(input: &[u8], limits: Limits) -> Result<Option<(Message, usize)>> {
+        Self::parse_with(input, false, limits)
+    }
+
+    // parse swaps invalid utf-8 for U+FFFD, which this errors on instead
+    pub fn parse_strict(input: &[u8]) -> Result<Option<(Message, usize)>> {
+        let Some((line, size)) = Self::next_line(input, false, Limits::default())? else {
             return Ok(None);
         };
 
-        if size > 510 {
-            // crlf is remaining 2
-            // this is a bit of a predicament.
-            // if we return just an Err, we'd could end up in an infinite loop,
-            // since nothing could be pulled off the buffer.
-            // clearing out the long message here isn't what parse should be doing.
-            // and panicing...
-            //
-            // we'll go with a simple Err, and, this situation could surface when the buffer fills up.
-            // in the future, it may be necessary to more clearly indicate the failure in order to allow
-            // the caller to do the cleaning themselves.
-            return Err(Error::MessageTooLong);
+        // every field is split out on ascii, so the line being valid means each field is
+        if core::str::from_utf8(line).is_err() {
+            return Err(Error::InvalidUtf8 { raw: line.to_vec() });
+        }
+
+        let message = RawMessage::scan(line)?.to_message(false);
+        Ok(Some((message, size)))
+    }
+
+    // unlike parse, which collapses runs of spaces between middle parameters, each extra space becomes an
+    // empty parameter, so that the exact middle parameters survive a round trip through to_bytes.
+    // mainly for proxies and loggers that need to reproduce what they received.
+    pub fn parse_raw(input: &[u8]) -> Result<Option<(Message, usize)>> {
+        let Some((line, size)) = Self::next_line(input, false, Limits::default())? else {
+            return Ok(None);
+        };
+
+        let message = RawMessage::scan(line)?.to_message(true);
+        Ok(Some((message, size)))
+    }
+
+    // stops at the first incomplete or bad line, leaving it for the caller to deal with
+    pub fn parse_all(input: &[u8]) -> (Vec<Message>, usize) {
+        let mut messages = Vec::new();
+        let mut consumed = 0;
+        while let Ok(Some((message, size))) = Self::parse(&input[consumed..]) {
+            messages.push(message);
+            consumed += size;
+        }
+        (messages, consumed)
+    }
+
+    // for reading off a socket: consumes the message from the buffer, leaving anything incomplete for next time.
+    // a line that fails to parse gets consumed too, since there's nothing else to do with it.
+    pub fn parse_from(buffer: &mut BytesMut) -> Result<Option<Message>> {
+        match Self::parse(buffer) {
+            Ok(Some((message, size))) => {
+                buffer.advance(size);
+                Ok(Some(message))
+            }
+            Ok(None) => Ok(None),
+            Err(err) => {
+                let skip = match err {
+                    Error::ParsedMessageTooLong { skip } => skip,
+                    // any other error means there was a complete line, which we drop
+                    _ => buffer
+                        .windows(2)
+                        .position(|w| w == b"\r\n")
+                        .map_or(buffer.len(), |p| p + 2),
+                };
+                buffer.advance(skip);
+                Err(err)
+            }
         }
+    }
+
+    fn parse_with(input: &[u8], lenient: bool, limits: Limits) -> Result<Option<(Message, usize)>> {
+        let Some((line, size)) = Self::next_line(input, lenient, limits)? else {
+            return Ok(None);
+        };
+
+        let message = RawMessage::scan(line)?.to_message(false);
+        Ok(Some((message, size)))
+    }
+
+    // the original regex-based parser, kept around for comparison against the hand-written one
+    #[cfg(feature = "regex-parser")]
+    pub fn parse_regex(input: &[u8]) -> Result<Option<(Message, usize)>> {
+        let Some(size) = Self::next_line(input, false, Limits::default())?.map(|(l, _)| l.len())
+        else {
+            return Ok(None);
+        };
 
         // this is technically more permissive than the spec
         // but, since we're expecting to parse valid messages, this is fine
@@ -221,10 +968,9 @@ impl Message {
         static R: LazyLock<Regex> = LazyLock::new(|| {
             Regex::new(
                 r"^(?x)
-                (?::(?: # prefix
-                    (?:(?<nick>[^!]+)!(?<user>[^@]+)@(?<server>[^\ ]+))
-                    | (?<serverprefix>[^\ ]+)
-                )\ +)?
+                (?:@(?<tags>[^\ ]+)\ +)? # tags
+
+                (?::(?<prefix>[^\ ]+)\ +)? # prefix
 
                 (?<command>[^\ ]+)
 
@@ -241,11 +987,13 @@ impl Message {
                 # if 14 params, then the colon is optional
                 # if >0 and <14, the colon is present
                 # in either case, due to how we match the initial parameters, this regex is sufficient
+                # without a colon, there has to be something there, since trailing spaces are ignored
                 (?:
-                    \ +:?
-                    (?<lastparam>[^\r\n]*)
+                    \ +:(?<lastparam>[^\r\n]*)
+                    |
+                    \ +(?<lastparam14>[^:\ \r\n][^\r\n]*)
                 )?
-                \r\n
+                \ *\r\n
                 ",
             )
             .unwrap()
@@ -262,45 +1010,148 @@ impl Message {
 
         // i have doubts as to whether or not this style is better than the more procedural version
         // but this was an attempt to push the style hard. we can change it later if desired.
-        R.captures(input)
+        R.captures(&input[..size + 2])
             .filter(|c| c.get(0).map(|m| !m.is_empty()).unwrap_or(false))
-            .map_or(Err(Error::Parsing), |c| Ok(Some((
-                Message::new_unchecked(
-                    cap(&c, "nick")
-                        .map(|n| Prefix::User(UserMask {
-                            nickname: n,
-                            user: cap(&c, "user").expect("If nick present, this must be present according to regex."),
-                            server: cap(&c, "server").expect("If nick present, this must be present according to regex.")
-                        }))
-                        .or_else(|| cap(&c, "serverprefix").map(Prefix::Server)),
+            .map_or(Err(Error::Parsing { raw: input[..size].to_vec(), component: Component::Line, offset: 0 }), |c| Ok(Some((
+                Message {
+                    tags: cap(&c, "tags").map(|t| parse_tags(&t)),
+                    ..Message::new_unchecked(
+                    cap(&c, "prefix").map(parse_prefix),
                     cap(&c, "command")
-                        .map(|c| c.parse().map_or(Command::General(c), Command::Numeric))
+                        .map(|c| parse_command(c.as_bytes()))
                         .expect("The regex has matched, so this non-optional capture can be unwrapped."),
                     cap(&c, "parameters")
                         .map(|p| Some(p.split_ascii_whitespace()
                             .map(|p| p.to_string())
                             .collect::<Vec<String>>()))
                         .unwrap_or(None),
-                    cap(&c, "lastparam"),
-                ),
-                size,
+                    cap(&c, "lastparam").or_else(|| cap(&c, "lastparam14")),
+                )},
+                size + 2, // crlf is consumed too
             ))))
     }
 
-    fn calc_len(
+    // finds the first line in the input, excluding the line terminator,
+    // along with how many bytes it takes up, including the line terminator
+    fn next_line(input: &[u8], lenient: bool, limits: Limits) -> Result<Option<(&[u8], usize)>> {
+        let terminated = if lenient {
+            input.iter().position(|b| *b == b'\n').map(|p| {
+                let line = &input[..p];
+                (line.strip_suffix(b"\r").unwrap_or(line), p + 1)
+            })
+        } else {
+            input
+                .windows(2)
+                .position(|w| w == b"\r\n")
+                .map(|p| (&input[..p], p + 2))
+        };
+
+        let Some((line, consumed)) = terminated else {
+            // if we don't have a complete line, there's simply incomplete data in the buffer
+            // which is not an error
+            return Ok(None);
+        };
+        let size = line.len();
+
+        // the tag portion, including the leading @ and trailing space, gets its own limit.
+        // note that if there are extra spaces after the tags, they end up counting against the rest of the message.
+        let tags_size = match line.first() {
+            Some(b'@') => line.iter().position(|b| *b == b' ').map_or(size, |p| p + 1),
+            _ => 0,
+        };
+
+        if tags_size > limits.max_tags || size - tags_size + 2 > limits.max_line {
+            // +2 for the crlf, which counts against the line limit
+            // if we return just an Err, callers could end up in an infinite loop,
+            // since nothing could be pulled off the buffer.
+            // clearing out the long message here isn't what parse should be doing,
+            // so we tell the caller how much to skip to do the cleaning themselves.
+            return Err(Error::ParsedMessageTooLong { skip: consumed });
+        }
+
+        Ok(Some((line, consumed)))
+    }
+
+    // whether to_bytes would succeed under the default limits
+    pub fn fits(&self) -> bool {
+        !self.exceeds_limits(Limits::default())
+    }
+
+    fn exceeds_limits(&self, limits: Limits) -> bool {
+        !self.wire_len().within(limits)
+    }
+
+    // how many bytes to_bytes would produce, where the total includes the tags and crlf
+    pub fn wire_len(&self) -> WireLen {
+        let tags = Self::calc_tags_len(&self.tags);
+        WireLen {
+            tags,
+            line: Self::calc_len(
+                &self.tags,
+                &self.prefix,
+                &self.command,
+                &self.parameters,
+                &self.last_parameter,
+                self.last_parameter_colon,
+            ) - tags,
+        }
+    }
+
+    fn calc_tags_len(tags: &Option<Vec<Tag>>) -> usize {
+        let Some(t) = tags else {
+            return 0;
+        };
+
+        let value_len = |v: &str| {
+            v.bytes()
+                .map(|b| escaped_tag_byte(b).map_or(1, |e| e.len()))
+                .sum::<usize>()
+        };
+
+        2 + // the leading @ and trailing space
+        t.len().saturating_sub(1) + // semicolon separators
+        t.iter().fold(0, |acc, cur| {
+            acc + cur.key.len() + cur.value.as_deref().map_or(0, |v| value_len(v) + 1) // equals sign
+        })
+    }
+
+    // after 14 middle parameters, everything else is the last parameter, so its colon can be dropped.
+    // that's only done if it was parsed without one, or omit_trailing_colon asked for it.
+    fn omits_colon(
+        parameters: &Option<Vec<String>>,
+        last_parameter: &Option<String>,
+        colon: Option<bool>,
+    ) -> bool {
+        let count = parameters.as_ref().map_or(0, |p| p.len());
+        let Some(l) = last_parameter else {
+            return false;
+        };
+
+        // parse_raw can end up with more than 14 because of empty parameters
+        colon == Some(false) && count >= 14 && !l.starts_with(':')
+    }
+
+    fn calc_len(
+        tags: &Option<Vec<Tag>>,
         prefix: &Option<Prefix>,
         command: &Command,
         parameters: &Option<Vec<String>>,
         last_parameter: &Option<String>,
+        last_parameter_colon: Option<bool>,
     ) -> usize {
         2 + // crlf
+        Self::calc_tags_len(tags) +
         match prefix {
             Some(Prefix::Server(s)) => s.len() + 2, // colon prefix + space
             Some(Prefix::User(UserMask {
                 nickname,
                 user,
                 server,
-            })) => nickname.len() + user.len() + server.len() + 4, // colon prefix, !, @, and space
+            })) => {
+                nickname.len() + 2 // colon prefix + space
+                    + user.as_ref().map_or(0, |u| u.len() + 1) // !
+                    + server.as_ref().map_or(0, |s| s.len() + 1) // @
+            }
             None => 0,
         } + match command {
             Command::General(s) => s.len(),
@@ -310,19 +1161,691 @@ impl Message {
             Some(p) => p.iter().fold(0, |acc, cur| acc + cur.len() + 1),
             None => 0
         } + match last_parameter {
-            Some(p) => p.len() + 2, // colon prefix, can consider the separating space a prefix
+            // colon prefix, can consider the separating space a prefix
+            Some(p) => {
+                p.len() + 2
+                    - usize::from(Self::omits_colon(
+                        parameters,
+                        last_parameter,
+                        last_parameter_colon,
+                    ))
+            }
             None => 0
         }
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+impl FromStr for Message {
+    type Err = Error;
+
+    // parses a single line, where the crlf is optional
+    fn from_str(s: &str) -> Result<Self> {
+        let line = match s.strip_suffix("\r\n") {
+            Some(_) => s.to_string(),
+            None => format!("{}\r\n", s),
+        };
+
+        match Message::parse(line.as_bytes())? {
+            Some((message, size)) if size == line.len() => Ok(message),
+            // more than one line
+            parsed => Err(Error::Parsing {
+                raw: s.as_bytes().to_vec(),
+                component: Component::Line,
+                offset: parsed.map_or(0, |(_, size)| size),
+            }),
+        }
+    }
+}
+
+impl Display for Message {
+    // the wire format, minus the crlf
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut b = Vec::with_capacity(self.wire_len().total());
+        self.write_unchecked(&mut b);
+        f.write_str(&String::from_utf8_lossy(&b[..b.len() - 2]))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MessageBuilder {
+    prefix: Option<Prefix>,
+    command: Option<Command>,
+    parameters: Vec<String>,
+    last_parameter: Option<String>,
+}
+
+impl MessageBuilder {
+    pub fn prefix(self, prefix: Prefix) -> Self {
+        MessageBuilder {
+            prefix: Some(prefix),
+            ..self
+        }
+    }
+
+    pub fn command(self, command: Command) -> Self {
+        MessageBuilder {
+            command: Some(command),
+            ..self
+        }
+    }
+
+    pub fn param(mut self, parameter: impl Into<String>) -> Self {
+        self.parameters.push(parameter.into());
+        self
+    }
+
+    // fails right away on a bad parameter, rather than at build, such as for parameters coming from user input.
+    // build still does the length check.
+    pub fn try_param(self, parameter: impl Into<String>) -> Result<Self> {
+        let parameter = parameter.into();
+        if Message::invalid_middle_parameter(&parameter) {
+            return Err(Error::CommandValidation);
+        }
+        if self.parameters.len() >= 14 {
+            return Err(Error::SimpleParameterValidation);
+        }
+        Ok(self.param(parameter))
+    }
+
+    pub fn trailing(self, last_parameter: impl Into<String>) -> Self {
+        MessageBuilder {
+            last_parameter: Some(last_parameter.into()),
+            ..self
+        }
+    }
+
+    pub fn build(self) -> Result<Message> {
+        Message::new(
+            self.prefix,
+            self.command.ok_or(Error::MissingCommand)?,
+            self.parameters,
+            self.last_parameter,
+        )
+    }
+}
+
+// the components of a single line, still borrowed from the input buffer.
+// this is as permissive as the old regex was, since we're expecting to parse valid messages,
+// and is also why we skip the validation that Message::new does.
+struct RawMessage<'a> {
+    tags: Option<&'a [u8]>,
+    prefix: Option<&'a [u8]>,
+    command: &'a [u8],
+    // the space-separated middle parameters, left unsplit
+    parameters: Option<&'a [u8]>,
+    last_parameter: Option<&'a [u8]>,
+    last_parameter_colon: bool,
+}
+
+impl<'a> RawMessage<'a> {
+    // the line should not include the crlf
+    fn scan(line: &'a [u8]) -> Result<RawMessage<'a>> {
+        // splits off the next space-delimited token, returning it, what comes after the spaces,
+        // and whether or not there were any spaces at all
+        fn token(input: &[u8]) -> (&[u8], &[u8], bool) {
+            let end = input.iter().position(|b| *b == b' ').unwrap_or(input.len());
+            let (token, rest) = input.split_at(end);
+            let spaces = rest.iter().take_while(|b| **b == b' ').count();
+            (token, &rest[spaces..], spaces > 0)
+        }
+
+        // everything we look at is the tail end of the line, so the offset is just how much came before it
+        let parsing = |component, at: &[u8]| Error::Parsing {
+            raw: line.to_vec(),
+            component,
+            offset: line.len() - at.len(),
+        };
+        // a lone cr or lf in the middle of a line could otherwise let a relay that re-serializes it inject another line
+        let stray =
+            |component, part: &[u8]| match part.iter().position(|b| *b == b'\r' || *b == b'\n') {
+                Some(p) => Err(Error::Parsing {
+                    raw: line.to_vec(),
+                    component,
+                    offset: part.as_ptr() as usize - line.as_ptr() as usize + p,
+                }),
+                None => Ok(()),
+            };
+        let mut rest = line;
+
+        // a line with only tags or a prefix is missing its command
+        let tags = match rest.strip_prefix(b"@") {
+            Some(r) => {
+                let (t, after, _) = token(r);
+                if t.is_empty() {
+                    return Err(parsing(Component::Tags, r));
+                }
+                stray(Component::Tags, t)?;
+                if after.is_empty() {
+                    return Err(parsing(Component::Command, after));
+                }
+                rest = after;
+                Some(t)
+            }
+            None => None,
+        };
+
+        let prefix = match rest.strip_prefix(b":") {
+            Some(r) => {
+                let (p, after, _) = token(r);
+                if p.is_empty() {
+                    return Err(parsing(Component::Prefix, r));
+                }
+                stray(Component::Prefix, p)?;
+                if after.is_empty() {
+                    return Err(parsing(Component::Command, after));
+                }
+                rest = after;
+                Some(p)
+            }
+            None => None,
+        };
+
+        let (command, mut rest, mut spaced) = token(rest);
+        if command.is_empty() {
+            return Err(parsing(Component::Command, rest));
+        }
+        stray(Component::Command, command)?;
+
+        let parameters_start = rest;
+        let mut parameters_len = 0;
+        let mut parameter_count = 0;
+        let mut last_parameter = None;
+        let mut last_parameter_colon = false;
+        while spaced {
+            // if 14 params, then the colon is optional
+            // if >0 and <14, the colon is present
+            // trailing spaces are ignored, rather than becoming an empty last parameter
+            if rest.is_empty() {
+                break;
+            }
+            if parameter_count == 14 || rest[0] == b':' {
+                let l = rest.strip_prefix(b":").unwrap_or(rest);
+                stray(Component::LastParameter, l)?;
+                last_parameter = Some(l);
+                last_parameter_colon = l.len() < rest.len();
+                break;
+            }
+
+            let (p, r, s) = token(rest);
+            stray(Component::Parameters, p)?;
+            parameter_count += 1;
+            parameters_len = parameters_start.len() - rest.len() + p.len();
+            (rest, spaced) = (r, s);
+        }
+
+        Ok(RawMessage {
+            tags,
+            prefix,
+            command,
+            parameters: (parameter_count > 0).then(|| &parameters_start[..parameters_len]),
+            last_parameter,
+            last_parameter_colon,
+        })
+    }
+
+    // preserving spaces means splitting on each one, such that runs of them become empty parameters
+    fn to_message(&self, preserve_spaces: bool) -> Message {
+        fn lossy(b: &[u8]) -> String {
+            String::from_utf8_lossy(b).into_owned()
+        }
+
+        Message {
+            tags: self.tags.map(|t| parse_tags(&lossy(t))),
+            prefix: self.prefix.map(|p| parse_prefix(lossy(p))),
+            command: parse_command(self.command),
+            parameters: self.parameters.map(|p| {
+                p.split(|b| *b == b' ')
+                    .filter(|p| preserve_spaces || !p.is_empty())
+                    .map(lossy)
+                    .collect()
+            }),
+            last_parameter: self.last_parameter.map(lossy),
+            last_parameter_colon: self.last_parameter.map(|_| self.last_parameter_colon),
+        }
+    }
+}
+
+// a zero-copy view of a message, for read-only consumers that don't need ownership.
+// unlike Message::parse, no lossy conversion can happen, so invalid utf-8 fails parsing.
+// tags are left escaped, since unescaping them would require allocating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageRef<'a> {
+    tags: Option<&'a str>,
+    prefix: Option<&'a str>,
+    command: &'a str,
+    parameters: Option<&'a str>,
+    last_parameter: Option<&'a str>,
+}
+
+impl<'a> MessageRef<'a> {
+    pub fn parse(input: &'a [u8]) -> Result<Option<(MessageRef<'a>, usize)>> {
+        let Some((line, size)) = Message::next_line(input, false, Limits::default())? else {
+            return Ok(None);
+        };
+
+        let raw = RawMessage::scan(line)?;
+        let line = core::str::from_utf8(line).map_err(|e| Error::Parsing {
+            raw: line.to_vec(),
+            component: Component::Line,
+            offset: e.valid_up_to(),
+        })?;
+
+        // the scanned parts all come from splitting on ascii, so they're always on char boundaries
+        let sub = |part: &[u8]| {
+            let start = part.as_ptr() as usize - line.as_ptr() as usize;
+            &line[start..start + part.len()]
+        };
+
+        Ok(Some((
+            MessageRef {
+                tags: raw.tags.map(sub),
+                prefix: raw.prefix.map(sub),
+                command: sub(raw.command),
+                parameters: raw.parameters.map(sub),
+                last_parameter: raw.last_parameter.map(sub),
+            },
+            size,
+        )))
+    }
+
+    pub fn tags(&self) -> Option<&'a str> {
+        self.tags
+    }
+
+    pub fn prefix(&self) -> Option<&'a str> {
+        self.prefix
+    }
+
+    pub fn command(&self) -> &'a str {
+        self.command
+    }
+
+    pub fn parameters(&self) -> impl Iterator<Item = &'a str> {
+        self.parameters
+            .into_iter()
+            .flat_map(|p| p.split(' '))
+            .filter(|p| !p.is_empty())
+    }
+
+    pub fn last_parameter(&self) -> Option<&'a str> {
+        self.last_parameter
+    }
+
+    pub fn to_owned(&self) -> Message {
+        Message {
+            tags: self.tags.map(parse_tags),
+            prefix: self.prefix.map(|p| parse_prefix(p.to_string())),
+            command: parse_command(self.command.as_bytes()),
+            parameters: self
+                .parameters
+                .map(|_| self.parameters().map(|p| p.to_string()).collect()),
+            last_parameter: self.last_parameter.map(|l| l.to_string()),
+            last_parameter_colon: None,
+        }
+    }
+}
+
+// the exact bytes of a line, including the crlf, for proxies that forward lines verbatim
+// and only sometimes care what's in them
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RawLine {
+    bytes: Vec<u8>,
+}
+
+impl RawLine {
+    // None if there isn't a complete line yet. like parse, it's up to the caller to advance the buffer.
+    // there's no length limit, since nothing is being interpreted.
+    pub fn next(input: &[u8]) -> Option<(RawLine, usize)> {
+        let size = input.windows(2).position(|w| w == b"\r\n")? + 2;
+        Some((
+            RawLine {
+                bytes: input[..size].to_vec(),
+            },
+            size,
+        ))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn as_message(&self) -> Result<Message> {
+        Message::parse(&self.bytes)?
+            .map(|(message, _)| message)
+            // can't happen, since we always have a complete line
+            .ok_or_else(|| Error::Parsing {
+                raw: self.bytes[..self.bytes.len() - 2].to_vec(),
+                component: Component::Line,
+                offset: 0,
+            })
+    }
+}
+
+// drains complete messages from the front of a growable buffer, leaving any partial line behind.
+// bad lines are yielded as errors but still removed, so that iterating can carry on past them.
+pub struct MessageIter<'a> {
+    buffer: &'a mut BytesMut,
+}
+
+impl<'a> MessageIter<'a> {
+    pub fn new(buffer: &'a mut BytesMut) -> Self {
+        MessageIter { buffer }
+    }
+}
+
+impl Iterator for MessageIter<'_> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Result<Message>> {
+        Message::parse_from(self.buffer).transpose()
+    }
+}
+
+// numerics are always exactly three digits, so something like `0001` or `+1` is just an odd command.
+// they're worked out straight from the bytes, since servers send a lot of them and there's no need for a String.
+fn parse_command(command: &[u8]) -> Command {
+    match command {
+        [_, _, _] if command.iter().all(|d| d.is_ascii_digit()) => {
+            Command::Numeric(command.iter().fold(0, |n, d| n * 10 + u16::from(d - b'0')))
+        }
+        _ => Command::General(String::from_utf8_lossy(command).into_owned()),
+    }
+}
+
+fn parse_prefix(prefix: String) -> Prefix {
+    let (rest, server) = match prefix.split_once('@') {
+        Some((rest, server)) => (rest, Some(server)),
+        None => (&prefix[..], None),
+    };
+    let (nickname, user) = match rest.split_once('!') {
+        Some((nickname, user)) => (nickname, Some(user)),
+        None => (rest, None),
+    };
+
+    // a lone nick and a server name look alike, but nicks can't have dots in them or start with a digit.
+    // the digit part is for ts6-style server ids, like `42A`.
+    let looks_like_server = user.is_none()
+        && server.is_none()
+        && (nickname.contains('.') || nickname.starts_with(|c: char| c.is_ascii_digit()));
+    if looks_like_server || nickname.is_empty() || user == Some("") || server == Some("") {
+        return Prefix::Server(prefix);
+    }
+
+    Prefix::User(UserMask {
+        nickname: nickname.to_string(),
+        user: user.map(|u| u.to_string()),
+        server: server.map(|s| s.to_string()),
+    })
+}
+
+// only the utc form the server-time spec mandates, and nothing before the unix epoch
+#[cfg(feature = "std")]
+fn parse_timestamp(timestamp: &str) -> Option<Duration> {
+    fn number(s: &str, len: usize) -> Option<u64> {
+        if s.len() == len && s.bytes().all(|b| b.is_ascii_digit()) {
+            s.parse().ok()
+        } else {
+            None
+        }
+    }
+
+    let (date, time) = timestamp.strip_suffix('Z')?.split_once('T')?;
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) => (time, Some(fraction)),
+        None => (time, None),
+    };
+
+    let [year, month, day] = match date.split('-').collect::<Vec<_>>()[..] {
+        [y, m, d] => [number(y, 4)?, number(m, 2)?, number(d, 2)?],
+        _ => return None,
+    };
+    let [hour, minute, second] = match time.split(':').collect::<Vec<_>>()[..] {
+        [h, m, s] => [number(h, 2)?, number(m, 2)?, number(s, 2)?],
+        _ => return None,
+    };
+    let nanos = match fraction {
+        Some(f) if (1..=9).contains(&f.len()) => {
+            number(f, f.len())? * 10u64.pow(9 - f.len() as u32)
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+
+    let leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 if leap_year => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    if year < 1970 || day == 0 || day > days_in_month || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    // days since the epoch, from howard hinnant's days_from_civil, with years starting in march
+    let y = if month <= 2 { year - 1 } else { year };
+    let (era, year_of_era) = (y / 400, y % 400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    Some(Duration::new(
+        days * 86400 + hour * 3600 + minute * 60 + second,
+        nanos as u32,
+    ))
+}
+
+fn parse_tags(tags: &str) -> Vec<Tag> {
+    tags.split(';')
+        .filter(|t| !t.is_empty())
+        .map(|t| match t.split_once('=') {
+            Some((key, value)) => Tag {
+                key: key.to_string(),
+                value: Some(unescape_tag_value(value)),
+            },
+            None => Tag {
+                key: t.to_string(),
+                value: None,
+            },
+        })
+        .collect()
+}
+
+// the whole escape sequence, like `\s` for a space
+fn escaped_tag_byte(b: u8) -> Option<&'static [u8]> {
+    match b {
+        b';' => Some(b"\\:"),
+        b' ' => Some(b"\\s"),
+        b'\\' => Some(b"\\\\"),
+        b'\r' => Some(b"\\r"),
+        b'\n' => Some(b"\\n"),
+        _ => None,
+    }
+}
+
+// the runs between escapes get written as-is, rather than a byte at a time
+fn escape_tag_value<'a, E>(
+    value: &'a str,
+    w: &mut impl FnMut(Cow<'a, [u8]>) -> core::result::Result<(), E>,
+) -> core::result::Result<(), E> {
+    let mut rest = value.as_bytes();
+    while let Some((i, e)) = rest
+        .iter()
+        .enumerate()
+        .find_map(|(i, b)| escaped_tag_byte(*b).map(|e| (i, e)))
+    {
+        w(rest[..i].into())?;
+        w(e.into())?;
+        rest = &rest[i + 1..];
+    }
+    w(rest.into())
+}
+
+fn unescape_tag_value(value: &str) -> String {
+    let mut s = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            s.push(c);
+            continue;
+        }
+
+        // per spec, unknown escapes drop the backslash, and a trailing backslash is dropped entirely
+        match chars.next() {
+            Some(':') => s.push(';'),
+            Some('s') => s.push(' '),
+            Some('r') => s.push('\r'),
+            Some('n') => s.push('\n'),
+            Some(c) => s.push(c),
+            None => (),
+        }
+    }
+    s
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tag {
+    pub key: String,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Command {
     Numeric(u16),
     General(String),
 }
 
-#[derive(Debug)]
+impl Command {
+    pub fn as_known(&self) -> Option<KnownCommand> {
+        match self {
+            Command::General(c) => KnownCommand::ALL
+                .iter()
+                .find(|k| k.as_str().eq_ignore_ascii_case(c))
+                .copied(),
+            Command::Numeric(_) => None,
+        }
+    }
+
+    // for numerics, the name must be all digits, like `001`
+    pub fn eq_ignore_case(&self, name: &str) -> bool {
+        match self {
+            Command::General(c) => c.eq_ignore_ascii_case(name),
+            Command::Numeric(n) => {
+                !name.is_empty()
+                    && name.bytes().all(|b| b.is_ascii_digit())
+                    && name.parse::<u16>().is_ok_and(|parsed| parsed == *n)
+            }
+        }
+    }
+}
+
+impl From<KnownCommand> for Command {
+    fn from(value: KnownCommand) -> Self {
+        Command::General(value.as_str().to_string())
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+pub enum KnownCommand {
+    Pass,
+    Nick,
+    User,
+    Oper,
+    Quit,
+    Join,
+    Part,
+    Mode,
+    Topic,
+    Names,
+    List,
+    Invite,
+    Kick,
+    Privmsg,
+    Notice,
+    Motd,
+    Who,
+    Whois,
+    Kill,
+    Ping,
+    Pong,
+    Error,
+    Away,
+    Cap,
+    Authenticate,
+    Batch,
+}
+
+impl KnownCommand {
+    pub(crate) const ALL: [KnownCommand; 26] = [
+        KnownCommand::Pass,
+        KnownCommand::Nick,
+        KnownCommand::User,
+        KnownCommand::Oper,
+        KnownCommand::Quit,
+        KnownCommand::Join,
+        KnownCommand::Part,
+        KnownCommand::Mode,
+        KnownCommand::Topic,
+        KnownCommand::Names,
+        KnownCommand::List,
+        KnownCommand::Invite,
+        KnownCommand::Kick,
+        KnownCommand::Privmsg,
+        KnownCommand::Notice,
+        KnownCommand::Motd,
+        KnownCommand::Who,
+        KnownCommand::Whois,
+        KnownCommand::Kill,
+        KnownCommand::Ping,
+        KnownCommand::Pong,
+        KnownCommand::Error,
+        KnownCommand::Away,
+        KnownCommand::Cap,
+        KnownCommand::Authenticate,
+        KnownCommand::Batch,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KnownCommand::Pass => "PASS",
+            KnownCommand::Nick => "NICK",
+            KnownCommand::User => "USER",
+            KnownCommand::Oper => "OPER",
+            KnownCommand::Quit => "QUIT",
+            KnownCommand::Join => "JOIN",
+            KnownCommand::Part => "PART",
+            KnownCommand::Mode => "MODE",
+            KnownCommand::Topic => "TOPIC",
+            KnownCommand::Names => "NAMES",
+            KnownCommand::List => "LIST",
+            KnownCommand::Invite => "INVITE",
+            KnownCommand::Kick => "KICK",
+            KnownCommand::Privmsg => "PRIVMSG",
+            KnownCommand::Notice => "NOTICE",
+            KnownCommand::Motd => "MOTD",
+            KnownCommand::Who => "WHO",
+            KnownCommand::Whois => "WHOIS",
+            KnownCommand::Kill => "KILL",
+            KnownCommand::Ping => "PING",
+            KnownCommand::Pong => "PONG",
+            KnownCommand::Error => "ERROR",
+            KnownCommand::Away => "AWAY",
+            KnownCommand::Cap => "CAP",
+            KnownCommand::Authenticate => "AUTHENTICATE",
+            KnownCommand::Batch => "BATCH",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Prefix {
     // we could hypothetically handle all the forms of server
     // but these are generally treated as a name, so no particular need
@@ -330,11 +1853,92 @@ pub enum Prefix {
     User(UserMask),
 }
 
-#[derive(Debug)]
+impl Prefix {
+    pub fn nick(&self) -> Option<&str> {
+        match self {
+            Prefix::Server(_) => None,
+            Prefix::User(mask) => Some(&mask.nickname),
+        }
+    }
+
+    // a ts6-style server id, like `42A`, which linked servers use in place of their names
+    pub fn is_sid(&self) -> bool {
+        match self {
+            Prefix::Server(s) => {
+                let s = s.as_bytes();
+                s.len() == 3
+                    && s[0].is_ascii_digit()
+                    && s[1..]
+                        .iter()
+                        .all(|b| b.is_ascii_digit() || b.is_ascii_uppercase())
+            }
+            Prefix::User(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UserMask {
     pub nickname: String,
-    pub user: String,
-    pub server: String,
+    pub user: Option<String>,
+    pub server: Option<String>,
+}
+
+impl UserMask {
+    // matches against a ban-style mask like `*!*@*.example.com`, where any missing components match anything
+    pub fn matches(&self, pattern: &str) -> bool {
+        let (nickname, rest) = match pattern.split_once('!') {
+            Some(split) => split,
+            None if pattern.contains('@') => ("*", pattern),
+            None => (pattern, "*"),
+        };
+        let (user, server) = rest.split_once('@').unwrap_or((rest, "*"));
+
+        // nicks use rfc1459 casemapping, where []\~ are the uppercase versions of {}|^
+        let nick_fold = |c: char| CaseMapping::Rfc1459.fold_char(c);
+        let ascii_fold = |c: char| c.to_ascii_lowercase();
+
+        glob_matches(nickname, &self.nickname, nick_fold)
+            && glob_matches(user, self.user.as_deref().unwrap_or_default(), ascii_fold)
+            && glob_matches(
+                server,
+                self.server.as_deref().unwrap_or_default(),
+                ascii_fold,
+            )
+    }
+}
+
+// `*` matches any sequence, and `?` matches any single char
+fn glob_matches(pattern: &str, text: &str, fold: impl Fn(char) -> char) -> bool {
+    let pattern = pattern.chars().map(&fold).collect::<Vec<_>>();
+    let text = text.chars().map(&fold).collect::<Vec<_>>();
+
+    let (mut p, mut t) = (0, 0);
+    // where to resume from if we need to backtrack and let the last star eat one more char
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, t));
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((bp, bt)) => {
+                    p = bp;
+                    t = bt + 1;
+                    backtrack = Some((bp, bt + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
 }
 
 #[cfg(test)]
@@ -418,42 +2022,604 @@ mod tests {
     }
 
     #[test]
-    fn parse_server_prefix() {
-        let raw = b":server-yay COMMAND\r\n";
+    fn serialize_fourteen_parameters_without_colon() {
+        let parameters = (1..=14).map(|i| i.to_string()).collect::<Vec<_>>();
+        let message = Message::new_unchecked(
+            None,
+            Command::General("COMMAND".to_string()),
+            Some(parameters.clone()),
+            Some("15".to_string()),
+        );
 
-        let Ok(Some((message, _))) = Message::parse(raw) else {
-            panic!("Unable to parse message")
-        };
+        // the colon is written unless asked otherwise
+        let bytes = message.to_bytes().unwrap();
+        assert_eq!(
+            b"COMMAND 1 2 3 4 5 6 7 8 9 10 11 12 13 14 :15\r\n",
+            &bytes[..]
+        );
+        assert_eq!(bytes.len(), message.wire_len().total());
 
+        let message = message.omit_trailing_colon();
+        let bytes = message.to_bytes().unwrap();
         assert_eq!(
-            Message::new_unchecked(
-                Some(Prefix::Server("server-yay".to_string())),
-                Command::General("COMMAND".to_string()),
-                None,
-                None
-            ),
-            message
+            b"COMMAND 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15\r\n",
+            &bytes[..]
         );
+        assert_eq!(bytes.len(), message.wire_len().total());
+
+        let Ok(Some((parsed, _))) = Message::parse(&bytes) else {
+            panic!("Unable to parse message")
+        };
+        assert_eq!(message, parsed);
+
+        // the colon stays when it's needed, or if there aren't exactly 14 middle parameters
+        for (parameters, last, expected) in [
+            (parameters.clone(), "15 16", &b" 14 :15 16\r\n"[..]),
+            (parameters.clone(), ":15", b" 14 ::15\r\n"),
+            (parameters.clone(), "", b" 14 :\r\n"),
+            (parameters[..13].to_vec(), "14", b" 13 :14\r\n"),
+        ] {
+            let message = Message::new_unchecked(
+                None,
+                Command::General("COMMAND".to_string()),
+                Some(parameters),
+                Some(last.to_string()),
+            )
+            .omit_trailing_colon();
+            let bytes = message.to_bytes().unwrap();
+            assert!(bytes.ends_with(expected));
+            assert_eq!(bytes.len(), message.wire_len().total());
+        }
     }
 
     #[test]
-    fn parse_user_prefix() {
-        let raw = b":nick!user@server COMMAND\r\n";
+    fn fourteen_parameters_colon_round_trip() {
+        for raw in [
+            &b"COMMAND 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17\r\n"[..],
+            b"COMMAND 1 2 3 4 5 6 7 8 9 10 11 12 13 14 :15\r\n",
+            b"COMMAND 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15\r\n",
+        ] {
+            let Ok(Some((message, _))) = Message::parse(raw) else {
+                panic!("Unable to parse message")
+            };
+            assert_eq!(raw, &message.to_bytes().unwrap()[..]);
+            assert_eq!(raw.len(), message.wire_len().total());
+        }
 
-        let Ok(Some((message, _))) = Message::parse(raw) else {
+        let Ok(Some((message, _))) =
+            Message::parse(b"COMMAND 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16\r\n")
+        else {
             panic!("Unable to parse message")
         };
+        assert_eq!(Some(false), message.last_parameter_had_colon());
 
-        assert_eq!(
-            Message::new_unchecked(
-                Some(Prefix::User(UserMask {
-                    nickname: "nick".to_string(),
-                    user: "user".to_string(),
-                    server: "server".to_string()
-                })),
-                Command::General("COMMAND".to_string()),
-                None,
-                None
+        let Ok(Some((message, _))) = Message::parse(b"PRIVMSG #chan :hi\r\n") else {
+            panic!("Unable to parse message")
+        };
+        assert_eq!(Some(true), message.last_parameter_had_colon());
+    }
+
+    #[test]
+    fn partial_user_prefixes() {
+        let cases = [
+            (":nick JOIN #chan\r\n", "nick", None, None),
+            (":nick@host JOIN #chan\r\n", "nick", None, Some("host")),
+            (
+                ":nick!user@host JOIN #chan\r\n",
+                "nick",
+                Some("user"),
+                Some("host"),
+            ),
+        ];
+
+        for (raw, nickname, user, server) in cases {
+            let Ok(Some((message, _))) = Message::parse(raw.as_bytes()) else {
+                panic!("Unable to parse message")
+            };
+
+            assert_eq!(
+                &Some(Prefix::User(UserMask {
+                    nickname: nickname.to_string(),
+                    user: user.map(|u| u.to_string()),
+                    server: server.map(|s| s.to_string()),
+                })),
+                message.prefix()
+            );
+
+            let bytes = message.to_bytes().unwrap();
+            assert_eq!(raw.as_bytes(), &bytes[..]);
+            assert_eq!(message.wire_len().total(), bytes.len());
+        }
+
+        // dots mean it's a server
+        let message: Message = ":irc.example.com NOTICE * :hi".parse().unwrap();
+        assert_eq!(
+            &Some(Prefix::Server("irc.example.com".to_string())),
+            message.prefix()
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn write_to_matches_to_bytes() {
+        for raw in [
+            "PING",
+            ":irc.server 001 me :Welcome",
+            ":nick!user@host PRIVMSG #chan :hi there",
+            "@a=b\\sc\\:d;e :nick PRIVMSG #chan :tagged",
+            "COMMAND 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15",
+        ] {
+            let message: Message = raw.parse().unwrap();
+            let mut written = Vec::new();
+            message.write_to(&mut written).unwrap();
+            assert_eq!(message.to_bytes().unwrap(), written, "{}", raw);
+            assert_eq!(written, message.segments().unwrap().concat());
+        }
+
+        let message = Message::new_unchecked(
+            None,
+            Command::General("PRIVMSG".to_string()),
+            Some(vec!["#chan".to_string()]),
+            Some("q".repeat(512)),
+        );
+        let mut written = Vec::new();
+        assert!(matches!(
+            message.write_to(&mut written),
+            Err(super::Error::MessageTooLong)
+        ));
+        assert!(written.is_empty());
+
+        // a writer running out of room is a serialization error, with the io error as its source
+        let mut short = [0u8; 4];
+        let result = "PING :hi"
+            .parse::<Message>()
+            .unwrap()
+            .write_to(&mut &mut short[..]);
+        let Err(super::Error::Serialization { io_error, .. }) = result else {
+            panic!("Expected a serialization error")
+        };
+        assert_eq!(io::ErrorKind::WriteZero, io_error.kind());
+    }
+
+    #[test]
+    fn wire_len_matches_bytes() {
+        for raw in [
+            "PING",
+            ":irc.server 001 me :Welcome",
+            ":nick!user@host PRIVMSG #chan :hi there",
+            ":nick@host JOIN #chan",
+            "@a=b\\sc;d :nick PRIVMSG #chan :tagged",
+            "COMMAND foo bar :",
+        ] {
+            let message: Message = raw.parse().unwrap();
+            assert_eq!(
+                message.to_bytes().unwrap().len(),
+                message.wire_len().total(),
+                "{}",
+                raw
+            );
+            assert!(message.fits());
+        }
+
+        let message = Message::new_unchecked(
+            None,
+            Command::General("PRIVMSG".to_string()),
+            Some(vec!["#chan".to_string()]),
+            Some("q".repeat(512)),
+        );
+        assert!(message.wire_len().total() > 512);
+        assert!(!message.fits());
+    }
+
+    #[test]
+    fn wire_len_separates_tags() {
+        let tags = (0..100)
+            .map(|i| Tag {
+                key: format!("key{i}"),
+                value: Some("v".repeat(30)),
+            })
+            .collect::<Vec<_>>();
+        let message: Message = "PRIVMSG #chan :hi".parse().unwrap();
+        let message = message.with_tags(Some(tags)).unwrap();
+
+        let len = message.wire_len();
+        let bytes = message.to_bytes().unwrap();
+        let space = bytes.iter().position(|b| *b == b' ').unwrap();
+        assert_eq!(space + 1, len.tags);
+        assert!(len.tags > 512);
+        assert_eq!(b"PRIVMSG #chan :hi\r\n".len(), len.line);
+        assert_eq!(bytes.len(), len.total());
+        assert!(len.within(Limits::default()));
+        assert!(message.fits());
+    }
+
+    #[test]
+    fn user_prefix_round_trip() {
+        let raw = ":nick!user@host PRIVMSG #chan :hi\r\n";
+        let Ok(Some((message, _))) = Message::parse(raw.as_bytes()) else {
+            panic!("Unable to parse message")
+        };
+
+        let bytes = message.to_bytes().unwrap();
+        assert_eq!(raw.as_bytes(), &bytes[..]);
+        assert_eq!(message.wire_len().total(), bytes.len());
+    }
+
+    // the user prefix used to be written without the space after it, running it into the command
+    #[test]
+    fn prefix_separated_from_command() {
+        let mask = |user: Option<&str>, server: Option<&str>| {
+            Prefix::User(UserMask {
+                nickname: "nick".to_string(),
+                user: user.map(str::to_string),
+                server: server.map(str::to_string),
+            })
+        };
+        for (prefix, expected) in [
+            (
+                Prefix::Server("irc.example.net".to_string()),
+                ":irc.example.net PING\r\n",
+            ),
+            (mask(None, None), ":nick PING\r\n"),
+            (mask(Some("user"), None), ":nick!user PING\r\n"),
+            (mask(None, Some("host")), ":nick@host PING\r\n"),
+            (mask(Some("user"), Some("host")), ":nick!user@host PING\r\n"),
+        ] {
+            let message = Message::new(
+                Some(prefix),
+                KnownCommand::Ping.into(),
+                None::<&str>,
+                None::<&str>,
+            )
+            .unwrap();
+            assert_eq!(expected.as_bytes(), &message.to_bytes().unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn parse_consumes_crlf() {
+        let raw = b"COMMAND\r\nNEXT\r\n";
+
+        let Ok(Some((_, size))) = Message::parse(raw) else {
+            panic!("Unable to parse message")
+        };
+        assert_eq!(9, size);
+
+        let Ok(Some((message, _))) = Message::parse(&raw[size..]) else {
+            panic!("Unable to parse second message")
+        };
+        assert_eq!(
+            Message::new_unchecked(None, Command::General("NEXT".to_string()), None, None),
+            message
+        );
+    }
+
+    // the reported size used to leave out the crlf, which left it at the start of the next read
+    #[test]
+    fn parse_size_includes_line_terminator() {
+        let raw = b"@a=b :nick!user@host PRIVMSG #chan :hi\r\nNEXT\r\n";
+        let first = raw.len() - b"NEXT\r\n".len();
+
+        type Parser = fn(&[u8]) -> Result<Option<(Message, usize)>>;
+        let parsers: [Parser; 4] = [
+            Message::parse,
+            Message::parse_lenient,
+            Message::parse_strict,
+            Message::parse_raw,
+        ];
+        for parse in parsers {
+            let Ok(Some((_, size))) = parse(raw) else {
+                panic!("Unable to parse message")
+            };
+            assert_eq!(first, size);
+        }
+
+        let Ok(Some((_, size))) = MessageRef::parse(raw) else {
+            panic!("Unable to parse message")
+        };
+        assert_eq!(first, size);
+
+        // a bare lf is only one byte
+        let Ok(Some((_, size))) = Message::parse_lenient(b"PING :a\nNEXT\r\n") else {
+            panic!("Unable to parse message")
+        };
+        assert_eq!(8, size);
+    }
+
+    #[test]
+    fn parse_all() {
+        let raw = b"PING :one\r\nPING :two\r\nPING :three\r\nPING :fo";
+
+        let (messages, consumed) = Message::parse_all(raw);
+        assert_eq!(
+            vec!["one", "two", "three"],
+            messages
+                .iter()
+                .map(|m| m.last_parameter().as_deref().unwrap())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(raw.len() - b"PING :fo".len(), consumed);
+    }
+
+    #[test]
+    fn parse_all_stops_at_malformed_line() {
+        let raw = b"PING :one\r\n:prefix\r\nPING :two\r\n";
+
+        let (messages, consumed) = Message::parse_all(raw);
+        assert_eq!(1, messages.len());
+        assert_eq!(11, consumed);
+    }
+
+    #[test]
+    fn message_iter() {
+        let mut buffer = BytesMut::from(&b"PING :one\r\nPING :two\r\nPING :thr"[..]);
+
+        let messages = MessageIter::new(&mut buffer)
+            .map(|m| m.unwrap().last_parameter().clone().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["one", "two"], messages);
+        assert_eq!(&b"PING :thr"[..], &buffer[..]);
+    }
+
+    #[test]
+    fn parse_from() {
+        let mut buffer = BytesMut::from(&b"PING :one\r\n:prefix\r\nPING :two\r\nPING :thr"[..]);
+
+        let one = Message::parse_from(&mut buffer).unwrap().unwrap();
+        assert_eq!(Some("one"), one.last_parameter().as_deref());
+        assert!(Message::parse_from(&mut buffer).is_err());
+        let two = Message::parse_from(&mut buffer).unwrap().unwrap();
+        assert_eq!(Some("two"), two.last_parameter().as_deref());
+
+        assert_eq!(None, Message::parse_from(&mut buffer).unwrap());
+        assert_eq!(&b"PING :thr"[..], &buffer[..]);
+    }
+
+    #[test]
+    fn raw_line_verbatim() {
+        let buffer = BytesMut::from(&b":nick!user@host  PRIVMSG   #chan  :hi there \r\nPING"[..]);
+
+        let (line, size) = RawLine::next(&buffer).unwrap();
+        assert_eq!(
+            b":nick!user@host  PRIVMSG   #chan  :hi there \r\n",
+            line.as_bytes()
+        );
+        assert_eq!(line.as_bytes().len(), size);
+        assert_eq!(Some("hi there "), line.as_message().unwrap().trailing());
+
+        assert!(RawLine::next(&buffer[size..]).is_none());
+
+        let (line, _) = RawLine::next(b":prefix-only\r\n").unwrap();
+        assert!(matches!(
+            line.as_message(),
+            Err(super::Error::Parsing { .. })
+        ));
+    }
+
+    #[test]
+    fn message_iter_skips_bad_lines() {
+        let long = format!("PRIVMSG #chan :{}\r\n", "a".repeat(900));
+        let mut buffer = BytesMut::from(&b":prefix\r\n"[..]);
+        buffer.extend_from_slice(long.as_bytes());
+        buffer.extend_from_slice(b"PING :after\r\n");
+
+        let mut iter = MessageIter::new(&mut buffer);
+        assert!(matches!(
+            iter.next(),
+            Some(Err(super::Error::Parsing { .. }))
+        ));
+        assert!(matches!(
+            iter.next(),
+            Some(Err(super::Error::ParsedMessageTooLong { .. }))
+        ));
+        assert_eq!(
+            &Some("after".to_string()),
+            iter.next().unwrap().unwrap().last_parameter()
+        );
+        assert!(iter.next().is_none());
+        assert!(buffer.is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let raw = b"@time=2023-01-01T00:00:00.000Z :nick!user@host PRIVMSG #chan :hi there\r\n";
+        let Ok(Some((message, _))) = Message::parse(raw) else {
+            panic!("Unable to parse message")
+        };
+
+        let json = serde_json::to_string(&message).unwrap();
+        assert!(json.contains(r#""command":{"General":"PRIVMSG"}"#));
+
+        let deserialized: Message = serde_json::from_str(&json).unwrap();
+        assert_eq!(message, deserialized);
+        assert_eq!(&raw[..], &deserialized.to_bytes().unwrap()[..]);
+
+        let numeric: Message = ":server 001 nick :Welcome".parse().unwrap();
+        let json = serde_json::to_string(&numeric).unwrap();
+        assert!(json.contains(r#""command":{"Numeric":1}"#));
+    }
+
+    #[test]
+    fn new_validates_command() {
+        let new = |c: &str| {
+            Message::new(
+                None,
+                Command::General(c.to_string()),
+                None::<&str>,
+                None::<&str>,
+            )
+        };
+
+        for invalid in ["", "PING2", "12x", "1234", "PRIV MSG", "PING\r\n"] {
+            assert!(
+                matches!(new(invalid), Err(super::Error::CommandValidation)),
+                "{:?} should be invalid",
+                invalid
+            );
+        }
+        assert!(new("PRIVMSG").is_ok());
+        assert!(new("001").is_ok());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn server_time() {
+        let time = |t: &str| {
+            format!("@time={} PING :hi", t)
+                .parse::<Message>()
+                .unwrap()
+                .server_time()
+        };
+
+        assert_eq!(
+            Some(SystemTime::UNIX_EPOCH + Duration::from_millis(1685622896789)),
+            time("2023-06-01T12:34:56.789Z")
+        );
+        assert_eq!(
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1709164800)),
+            time("2024-02-29T00:00:00Z")
+        );
+
+        for malformed in [
+            "yesterday",
+            "2023-06-01T12:34:56.789",
+            "2023-06-01 12:34:56Z",
+            "2023-02-29T00:00:00Z",
+            "2023-06-01T24:00:00Z",
+            "2023-6-01T12:34:56Z",
+            "2023-06-01T12:34:56.Z",
+            "1969-12-31T23:59:59Z",
+        ] {
+            assert_eq!(None, time(malformed), "{}", malformed);
+        }
+
+        assert_eq!(None, "PING :hi".parse::<Message>().unwrap().server_time());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn server_time_utc() {
+        let message: Message = "@time=2023-06-01T12:34:56.789Z PING :hi".parse().unwrap();
+        assert_eq!(
+            "2023-06-01T12:34:56.789+00:00",
+            message.server_time_utc().unwrap().to_rfc3339()
+        );
+    }
+
+    #[test]
+    fn slice_accessors() {
+        let message: Message = ":irc.server PRIVMSG #chan other :hi".parse().unwrap();
+        assert_eq!(
+            &["#chan".to_string(), "other".to_string()],
+            message.params()
+        );
+        assert_eq!(Some("hi"), message.trailing());
+        assert_eq!(
+            Some(&Prefix::Server("irc.server".to_string())),
+            message.prefix_ref()
+        );
+
+        let message: Message = "QUIT".parse().unwrap();
+        assert!(message.params().is_empty());
+        assert_eq!(None, message.trailing());
+        assert_eq!(None, message.prefix_ref());
+    }
+
+    #[test]
+    fn parse_lenient() {
+        let expected =
+            Message::new_unchecked(None, Command::General("COMMAND".to_string()), None, None);
+
+        for (raw, expected_size) in [(&b"COMMAND\n"[..], 8), (b"COMMAND\r\n", 9)] {
+            let Ok(Some((message, size))) = Message::parse_lenient(raw) else {
+                panic!("Unable to parse message")
+            };
+            assert_eq!(expected, message);
+            assert_eq!(expected_size, size);
+        }
+
+        // strict parsing still waits for the crlf
+        assert!(Message::parse(b"COMMAND\n").unwrap().is_none());
+    }
+
+    #[test]
+    fn parse_strict_utf8() {
+        let raw = b"PRIVMSG #chan :caf\xe9\r\n";
+
+        let Ok(Some((message, _))) = Message::parse(raw) else {
+            panic!("Unable to parse message")
+        };
+        assert_eq!(Some("caf\u{fffd}"), message.trailing());
+
+        assert!(matches!(
+            Message::parse_strict(raw),
+            Err(super::Error::InvalidUtf8 { raw }) if raw == b"PRIVMSG #chan :caf\xe9"
+        ));
+
+        let Ok(Some((message, _))) = Message::parse_strict("PRIVMSG #chan :café\r\n".as_bytes())
+        else {
+            panic!("Unable to parse message")
+        };
+        assert_eq!(Some("café"), message.trailing());
+    }
+
+    #[test]
+    fn parse_empty_trailing() {
+        for (raw, expected) in [
+            (&b"PRIVMSG #chan :\r\n"[..], Some(String::new())),
+            (b"PRIVMSG #chan\r\n", None),
+        ] {
+            let Ok(Some((message, _))) = Message::parse(raw) else {
+                panic!("Unable to parse message")
+            };
+            assert_eq!(&expected, message.last_parameter());
+            assert_eq!(raw, &message.to_bytes().unwrap()[..]);
+
+            let Ok(Some((message, _))) = MessageRef::parse(raw) else {
+                panic!("Unable to parse message")
+            };
+            assert_eq!(expected.as_deref(), message.last_parameter());
+        }
+    }
+
+    #[test]
+    fn parse_server_prefix() {
+        let raw = b":irc.server-yay COMMAND\r\n";
+
+        let Ok(Some((message, _))) = Message::parse(raw) else {
+            panic!("Unable to parse message")
+        };
+
+        assert_eq!(
+            Message::new_unchecked(
+                Some(Prefix::Server("irc.server-yay".to_string())),
+                Command::General("COMMAND".to_string()),
+                None,
+                None
+            ),
+            message
+        );
+    }
+
+    #[test]
+    fn parse_user_prefix() {
+        let raw = b":nick!user@server COMMAND\r\n";
+
+        let Ok(Some((message, _))) = Message::parse(raw) else {
+            panic!("Unable to parse message")
+        };
+
+        assert_eq!(
+            Message::new_unchecked(
+                Some(Prefix::User(UserMask {
+                    nickname: "nick".to_string(),
+                    user: Some("user".to_string()),
+                    server: Some("server".to_string())
+                })),
+                Command::General("COMMAND".to_string()),
+                None,
+                None
             ),
             message
         );
@@ -478,17 +2644,361 @@ mod tests {
             message
         );
 
-        Ok(())
+        Ok(())
+    }
+
+    #[test]
+    fn parse_size_over_512() {
+        let command = iter::repeat(b'A').take(511).collect::<Vec<u8>>();
+        let raw = [&command[..], &b"\r\n"[..]].concat();
+
+        match Message::parse(&raw[..]) {
+            Err(super::Error::ParsedMessageTooLong { skip }) => assert_eq!(raw.len(), skip),
+            result => panic!("Somehow parsed >512-byte message: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn parse_with_custom_limits() {
+        let raw = [&b"A".repeat(1022)[..], &b"\r\n"[..]].concat();
+        let limits = Limits {
+            max_line: 1024,
+            ..Limits::default()
+        };
+
+        assert!(matches!(
+            Message::parse(&raw[..]),
+            Err(super::Error::ParsedMessageTooLong { .. })
+        ));
+        let Ok(Some((message, size))) = Message::parse_with_limits(&raw[..], limits) else {
+            panic!("Unable to parse 1024-byte message")
+        };
+        assert_eq!(1024, size);
+        assert_eq!(&Command::General("A".repeat(1022)), message.command());
+
+        let raw = [&b"A".repeat(1023)[..], &b"\r\n"[..]].concat();
+        match Message::parse_with_limits(&raw[..], limits) {
+            Err(super::Error::ParsedMessageTooLong { skip }) => assert_eq!(raw.len(), skip),
+            result => panic!("Somehow parsed >1024-byte message: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn new_with_custom_limits() {
+        let limits = Limits {
+            max_line: 1024,
+            ..Limits::default()
+        };
+        let new = |len: usize, limits: Limits| {
+            Message::new_with_limits(
+                None,
+                Command::General("PRIVMSG".to_string()),
+                ["#chan"],
+                Some("q".repeat(len - 7 - 6 - 2 - 2)), // 2 for ` :`, 2 for crlf
+                limits,
+            )
+        };
+
+        assert!(matches!(
+            new(513, Limits::default()),
+            Err(super::Error::MessageTooLong)
+        ));
+        let message = new(1024, limits).unwrap();
+        assert!(matches!(
+            message.to_bytes(),
+            Err(super::Error::MessageTooLong)
+        ));
+        assert_eq!(1024, message.to_bytes_with_limits(limits).unwrap().len());
+        assert!(matches!(
+            new(1025, limits),
+            Err(super::Error::MessageTooLong)
+        ));
+    }
+
+    #[test]
+    fn clone_parsed_message() {
+        let raw = b"@time=2023-01-01T00:00:00.000Z :nick!user@server PRIVMSG #chan :hi\r\n";
+
+        let Ok(Some((message, _))) = Message::parse(raw) else {
+            panic!("Unable to parse message")
+        };
+
+        assert_eq!(message, message.clone());
+    }
+
+    #[test]
+    fn parse_malformed() {
+        for raw in [
+            &b"\r\n"[..],
+            b"@tags\r\n",
+            b"@tags \r\n",
+            b":prefix\r\n",
+            b"@tags :prefix\r\n",
+            b"COMMAND :trailing with \n in it\r\n",
+        ] {
+            assert!(
+                matches!(Message::parse(raw), Err(super::Error::Parsing { .. })),
+                "Parsed malformed message: {:?}",
+                String::from_utf8_lossy(raw)
+            );
+        }
+    }
+
+    #[test]
+    fn parse_error_component_and_offset() {
+        for (raw, expected_component, expected_offset) in [
+            (&b"\r\n"[..], Component::Command, 0),
+            (b"@ COMMAND\r\n", Component::Tags, 1),
+            (b"@tags\r\n", Component::Command, 5),
+            (b": COMMAND\r\n", Component::Prefix, 1),
+            (b"@a=b :prefix\r\n", Component::Command, 12),
+            (
+                b"COMMAND foo :bad\rtrailing\r\n",
+                Component::LastParameter,
+                16,
+            ),
+        ] {
+            match Message::parse(raw) {
+                Err(super::Error::Parsing {
+                    component, offset, ..
+                }) => {
+                    assert_eq!(
+                        (expected_component, expected_offset),
+                        (component, offset),
+                        "{:?}",
+                        String::from_utf8_lossy(raw)
+                    )
+                }
+                result => panic!("Parsed malformed message: {:?}", result),
+            }
+        }
+
+        match MessageRef::parse(b"PRIVMSG #chan :\xff\r\n") {
+            Err(super::Error::Parsing {
+                component, offset, ..
+            }) => assert_eq!((Component::Line, 15), (component, offset)),
+            result => panic!("Parsed malformed message: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn parse_rejects_stray_cr_lf() {
+        for (raw, expected_component, expected_offset) in [
+            (
+                &b"PRIVMSG #chan :hi\nQUIT :injected\r\n"[..],
+                Component::LastParameter,
+                17,
+            ),
+            (b"PRIVMSG #ch\ran :hi\r\n", Component::Parameters, 11),
+            (b"PRIV\nMSG #chan :hi\r\n", Component::Command, 4),
+            (
+                b":nick\n!user@host PRIVMSG #chan :hi\r\n",
+                Component::Prefix,
+                5,
+            ),
+            (b"@a=b\n :nick PRIVMSG #chan :hi\r\n", Component::Tags, 4),
+        ] {
+            match Message::parse(raw) {
+                Err(super::Error::Parsing {
+                    component, offset, ..
+                }) => assert_eq!(
+                    (expected_component, expected_offset),
+                    (component, offset),
+                    "{:?}",
+                    String::from_utf8_lossy(raw)
+                ),
+                result => panic!("Parsed malformed message: {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_error_has_raw_line() {
+        let raw = b"PING :ok\r\n@tags :prefix\r\n";
+
+        let Ok(Some((_, size))) = Message::parse(raw) else {
+            panic!("Unable to parse message")
+        };
+        match Message::parse(&raw[size..]) {
+            Err(super::Error::Parsing { raw, .. }) => assert_eq!(b"@tags :prefix", &raw[..]),
+            result => panic!("Parsed malformed message: {:?}", result),
+        }
+
+        match "PING :x\r\nPONG".parse::<Message>() {
+            Err(super::Error::Parsing { raw, .. }) => assert_eq!(b"PING :x\r\nPONG", &raw[..]),
+            result => panic!("Parsed malformed message: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn parse_extra_spaces() {
+        let raw = b":irc.server   COMMAND   foo    bar   :baz\r\n";
+
+        let Ok(Some((message, _))) = Message::parse(raw) else {
+            panic!("Unable to parse message")
+        };
+
+        assert_eq!(
+            Message::new_unchecked(
+                Some(Prefix::Server("irc.server".to_string())),
+                Command::General("COMMAND".to_string()),
+                Some(vec!["foo".to_string(), "bar".to_string()]),
+                Some("baz".to_string())
+            ),
+            message
+        );
+    }
+
+    #[test]
+    fn parse_raw_preserves_spaces() {
+        let raw = b"COMMAND foo  bar   baz :t\r\n";
+
+        let Ok(Some((message, _))) = Message::parse_raw(raw) else {
+            panic!("Unable to parse message")
+        };
+        assert_eq!(&["foo", "", "bar", "", "", "baz"][..], message.params());
+        assert_eq!(&raw[..], &message.to_bytes().unwrap()[..]);
+
+        let Ok(Some((message, _))) = Message::parse(raw) else {
+            panic!("Unable to parse message")
+        };
+        assert_eq!(&["foo", "bar", "baz"][..], message.params());
+    }
+
+    #[test]
+    fn parse_ignores_trailing_spaces() {
+        let Ok(Some((message, size))) = Message::parse(b"COMMAND foo  \r\n") else {
+            panic!("Unable to parse message")
+        };
+        assert_eq!(15, size);
+        assert_eq!(&Command::General("COMMAND".to_string()), message.command());
+        assert_eq!(&["foo"][..], message.params());
+        assert_eq!(None, message.trailing());
+
+        // but an explicit colon is still an empty trailing parameter
+        let message: Message = "COMMAND foo :".parse().unwrap();
+        assert_eq!(Some(""), message.trailing());
+    }
+
+    #[cfg(feature = "regex-parser")]
+    #[test]
+    fn parse_matches_regex() {
+        for raw in [
+            &b"COMMAND\r\n"[..],
+            b"COMMAND :\r\n",
+            b"COMMAND foo \r\n",
+            b"COMMAND foo  \r\n",
+            b"COMMAND 1 2 3 4 5 6 7 8 9 10 11 12 13 14 \r\n",
+            b"COMMAND 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 \r\n",
+            b"COMMAND foo ba:r baz: :yay\r\n",
+            b"COMMAND 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17\r\n",
+            b"COMMAND 1 2 3 4 5 6 7 8 9 10 11 12 13 14 :15 16 17\r\n",
+            b":server   COMMAND   foo    bar   :baz\r\n",
+            b":nick!user@server 001 me :Welcome\r\n",
+            b":nick!user!name@server@host PRIVMSG #chan :hi\r\n",
+            b"@time=2023-01-01T00:00:00.000Z;a=\\:\\s;b :nick!user@server PRIVMSG #chan :hi\r\n",
+            b"COMMAND\r\nNEXT\r\n",
+            b"\r\n",
+            b"INCOMPLETE",
+        ] {
+            let hand_written = Message::parse(raw);
+            let regex = Message::parse_regex(raw);
+            match (hand_written, regex) {
+                (Ok(h), Ok(r)) => assert_eq!(h, r),
+                (
+                    Err(super::Error::Parsing { raw: h, .. }),
+                    Err(super::Error::Parsing { raw: r, .. }),
+                ) => {
+                    assert_eq!(h, r)
+                }
+                (h, r) => panic!(
+                    "Mismatch for {:?}: {:?} vs {:?}",
+                    String::from_utf8_lossy(raw),
+                    h,
+                    r
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_ref() {
+        let raw = b"@a=\\s :nick!user@server PRIVMSG #chan  other :hello there\r\nNEXT\r\n";
+
+        let Ok(Some((message, size))) = MessageRef::parse(raw) else {
+            panic!("Unable to parse message")
+        };
+
+        assert_eq!(Some("a=\\s"), message.tags());
+        assert_eq!(Some("nick!user@server"), message.prefix());
+        assert_eq!("PRIVMSG", message.command());
+        assert_eq!(
+            vec!["#chan", "other"],
+            message.parameters().collect::<Vec<_>>()
+        );
+        assert_eq!(Some("hello there"), message.last_parameter());
+
+        let Ok(Some((owned, owned_size))) = Message::parse(raw) else {
+            panic!("Unable to parse message")
+        };
+        assert_eq!(owned, message.to_owned());
+        assert_eq!(owned_size, size);
+    }
+
+    #[test]
+    fn parse_ref_invalid_utf8() {
+        let raw = b"PRIVMSG #chan :\xff\r\n";
+
+        assert!(matches!(
+            MessageRef::parse(raw),
+            Err(super::Error::Parsing { .. })
+        ));
+        assert!(Message::parse(raw).is_ok());
+    }
+
+    #[test]
+    fn from_str() {
+        let expected = Message::new_unchecked(
+            None,
+            Command::General("PRIVMSG".to_string()),
+            Some(vec!["#chan".to_string()]),
+            Some("hi".to_string()),
+        );
+
+        assert_eq!(expected, "PRIVMSG #chan :hi".parse::<Message>().unwrap());
+        assert_eq!(
+            expected,
+            "PRIVMSG #chan :hi\r\n".parse::<Message>().unwrap()
+        );
+
+        assert!(matches!(
+            "PRIVMSG #chan :hi\r\nPING :x".parse::<Message>(),
+            Err(super::Error::Parsing { .. })
+        ));
+        assert!("".parse::<Message>().is_err());
     }
 
     #[test]
-    fn parse_size_over_512() {
-        let command = iter::repeat(b'A').take(511).collect::<Vec<u8>>();
-        let raw = [&command[..], &b"\r\n"[..]].concat();
-
-        if let Ok(Some((message, _))) = Message::parse(&raw[..]) {
-            panic!("Somehow parsed >512-byte message: {:?}", message)
-        }
+    fn display() {
+        let message = Message::new_unchecked(
+            Some(Prefix::Server("irc.server".to_string())),
+            Command::Numeric(1),
+            Some(vec!["me".to_string()]),
+            Some("Welcome".to_string()),
+        )
+        .with_tags(Some(vec![Tag {
+            key: "a".to_string(),
+            value: Some("b c".to_string()),
+        }]))
+        .unwrap();
+
+        let displayed = message.to_string();
+        assert_eq!("@a=b\\sc :irc.server 001 me :Welcome", displayed);
+        assert_eq!(
+            message.to_bytes().unwrap(),
+            format!("{}\r\n", displayed).into_bytes()
+        );
+        assert_eq!(message, displayed.parse().unwrap());
     }
 
     #[test]
@@ -506,12 +3016,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn serialize_into_non_empty_buffer() {
+        let message = Message::new_unchecked(
+            None,
+            Command::General("Command".to_string()),
+            Some(vec!["foo".to_string()]),
+            Some("bar".to_string()),
+        );
+
+        let mut b = b"PING :x\r\n".to_vec();
+        message.to_bytes_into(&mut b).unwrap();
+        assert_eq!(b"PING :x\r\nCommand foo :bar\r\n", &b[..]);
+
+        let too_long = Message::new_unchecked(
+            None,
+            Command::General("Command".to_string()),
+            None,
+            Some("q".repeat(512)),
+        );
+        assert!(too_long.to_bytes_into(&mut b).is_err());
+        assert_eq!(b"PING :x\r\nCommand foo :bar\r\n", &b[..]);
+    }
+
     #[test]
     fn serialize_size_512_message() {
         // we use all fields because we want to ensure everything is accounted for correctly
-        // it should be noted that we *could* save a byte if there are 14 middle parameters
-        // by dropping the colon of the last parameter, but this would be so unusual as to not be worth doing
-        // though not hard to do, to be fair.
         let message = Message::new_unchecked(
             Some(Prefix::Server("server".to_string())), //`:server `=8
             Command::General("Command".to_string()),    //`Command`=7
@@ -528,9 +3058,6 @@ mod tests {
     #[test]
     fn serialize_over_size_512_message() {
         // we use all fields because we want to ensure everything is accounted for correctly
-        // it should be noted that we *could* save a byte if there are 14 middle parameters
-        // by dropping the colon of the last parameter, but this would be so unusual as to not be worth doing
-        // though not hard to do, to be fair.
         let message = Message::new_unchecked(
             Some(Prefix::Server("server".to_string())), //`:server `=8
             Command::General("Command".to_string()),    //`Command`=7
@@ -546,29 +3073,735 @@ mod tests {
         }
     }
 
-    // these impls arent meant for public use but are convenient to use here
-    impl PartialEq for Message {
-        fn eq(&self, other: &Self) -> bool {
-            self.prefix == other.prefix
-                && self.command == other.command
-                && self.parameters == other.parameters
-                && self.last_parameter == other.last_parameter
+    #[test]
+    fn serialize_tags_round_trip() {
+        let message = Message::new_unchecked(
+            Some(Prefix::Server("irc.server".to_string())),
+            Command::General("COMMAND".to_string()),
+            None,
+            Some("yay".to_string()),
+        )
+        .with_tags(Some(vec![Tag {
+            key: "time".to_string(),
+            value: Some("2023-01-01T00:00:00.000Z".to_string()),
+        }]))
+        .unwrap();
+
+        let bytes = message.to_bytes().unwrap();
+        assert_eq!(
+            b"@time=2023-01-01T00:00:00.000Z :irc.server COMMAND :yay\r\n",
+            &bytes[..]
+        );
+        assert_eq!(bytes.len(), bytes.capacity());
+
+        let Ok(Some((parsed, _))) = Message::parse(&bytes) else {
+            panic!("Unable to parse message")
+        };
+        assert_eq!(message, parsed);
+    }
+
+    #[test]
+    fn serialize_escaped_tags() {
+        let message =
+            Message::new_unchecked(None, Command::General("COMMAND".to_string()), None, None)
+                .with_tags(Some(vec![
+                    Tag {
+                        key: "a".to_string(),
+                        value: Some("; \\\r\n".to_string()),
+                    },
+                    Tag {
+                        key: "b".to_string(),
+                        value: None,
+                    },
+                ]))
+                .unwrap();
+
+        let bytes = message.to_bytes().unwrap();
+        assert_eq!(b"@a=\\:\\s\\\\\\r\\n;b COMMAND\r\n", &bytes[..]);
+        assert_eq!(bytes.len(), bytes.capacity());
+
+        let Ok(Some((parsed, _))) = Message::parse(&bytes) else {
+            panic!("Unable to parse message")
+        };
+        assert_eq!(message, parsed);
+
+        // anything that parses can be put back on, nul included
+        let Ok(Some((parsed, _))) = Message::parse(b"@a=x\0y COMMAND\r\n") else {
+            panic!("Unable to parse message")
+        };
+        let rebuilt = parsed.clone().with_tags(parsed.tags().clone()).unwrap();
+        assert_eq!(parsed, rebuilt);
+    }
+
+    #[test]
+    fn serialize_tags_do_not_count_against_512() {
+        let message = Message::new_unchecked(
+            None,
+            Command::General("COMMAND".to_string()),
+            None,
+            Some("q".repeat(512 - 7 - 2 - 2)),
+        )
+        .with_tags(Some(vec![Tag {
+            key: "a".to_string(),
+            value: Some("q".repeat(4096 - 4)), // `@a=` and trailing space
+        }]))
+        .unwrap();
+
+        let bytes = message.to_bytes().unwrap();
+        assert_eq!(4096 + 512, bytes.len());
+        assert!(Message::parse(&bytes).unwrap().is_some());
+
+        let result = message.with_tags(Some(vec![Tag {
+            key: "a".to_string(),
+            value: Some("q".repeat(4096 - 4 + 1)),
+        }]));
+        assert!(matches!(result, Err(super::Error::MessageTooLong)));
+    }
+
+    #[test]
+    fn builder_privmsg() {
+        let built = Message::builder()
+            .prefix(Prefix::Server("server".to_string()))
+            .command(Command::General("PRIVMSG".to_string()))
+            .param("#chan")
+            .trailing("hello there")
+            .build()
+            .unwrap();
+
+        let expected = Message::new(
+            Some(Prefix::Server("server".to_string())),
+            Command::General("PRIVMSG".to_string()),
+            vec!["#chan".to_string()],
+            Some("hello there".to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(expected, built);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn map_trailing() {
+        let Ok(Some((message, _))) = Message::parse(
+            b"@time=x :nick!user@host PRIVMSG #chan :\x02bold\x02 and \x0304red\r\n",
+        ) else {
+            panic!("Unable to parse message")
+        };
+        let stripped = message
+            .clone()
+            .map_trailing(|t| crate::formatting::strip(&t))
+            .unwrap();
+        assert_eq!(
+            b"@time=x :nick!user@host PRIVMSG #chan :bold and red\r\n",
+            &stripped.to_bytes().unwrap()[..]
+        );
+        assert_eq!(message.tags(), stripped.tags());
+        assert_eq!(message.prefix(), stripped.prefix());
+        assert_eq!(message.params(), stripped.params());
+
+        // without a trailing parameter, nothing changes
+        for raw in [&b"PRIVMSG #chan hi\r\n"[..], b"JOIN #chan\r\n", b"PING\r\n"] {
+            let Ok(Some((message, _))) = Message::parse(raw) else {
+                panic!("Unable to parse message")
+            };
+            assert_eq!(
+                raw,
+                &message
+                    .map_trailing(|t| t.to_uppercase())
+                    .unwrap()
+                    .to_bytes()
+                    .unwrap()[..]
+            );
+        }
+    }
+
+    #[test]
+    fn new_rejects_any_cr_or_lf_in_last_parameter() {
+        for bad in ["a\r\nQUIT", "a\rQUIT", "a\nQUIT"] {
+            assert!(matches!(
+                Message::new(None, KnownCommand::Privmsg.into(), ["#chan"], Some(bad)),
+                Err(super::Error::LastParameterValidation)
+            ));
         }
     }
-    impl PartialEq for Prefix {
-        fn eq(&self, other: &Self) -> bool {
-            match (self, other) {
-                (Self::Server(l0), Self::Server(r0)) => l0 == r0,
-                (Self::User(l0), Self::User(r0)) => l0 == r0,
-                _ => false,
-            }
+
+    #[test]
+    fn with_trailing() {
+        let message =
+            Message::new(None, KnownCommand::Privmsg.into(), ["#chan"], Some("hi")).unwrap();
+        assert_eq!(
+            b"PRIVMSG #chan :bye\r\n",
+            &message
+                .clone()
+                .with_trailing("bye")
+                .unwrap()
+                .to_bytes()
+                .unwrap()[..]
+        );
+        for bad in ["a\r\nQUIT", "a\rQUIT", "a\nQUIT"] {
+            assert!(matches!(
+                message.clone().with_trailing(bad),
+                Err(super::Error::LastParameterValidation)
+            ));
+        }
+
+        // a last parameter parsed without a colon gets one again, since the new text might need it
+        let Ok(Some((parsed, _))) =
+            Message::parse(b"COMMAND 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15\r\n")
+        else {
+            panic!("Unable to parse message")
+        };
+        let replaced = parsed.with_trailing("16 17").unwrap();
+        assert_eq!(None, replaced.last_parameter_had_colon());
+        assert_eq!(
+            b"COMMAND 1 2 3 4 5 6 7 8 9 10 11 12 13 14 :16 17\r\n",
+            &replaced.to_bytes().unwrap()[..]
+        );
+        assert!(matches!(
+            message.with_trailing("a".repeat(600)),
+            Err(super::Error::MessageTooLong)
+        ));
+    }
+
+    #[test]
+    fn into_and_from_parts() {
+        let message: Message = ":nick!user@host PRIVMSG #chan :hello there"
+            .parse()
+            .unwrap();
+
+        let (prefix, command, parameters, last_parameter) = message.clone().into_parts();
+        assert_eq!(KnownCommand::Privmsg, command.as_known().unwrap());
+        assert_eq!(Some(vec!["#chan".to_string()]), parameters);
+
+        let last_parameter = last_parameter.map(|l| l.replace("there", "again"));
+        let modified = Message::from_parts(prefix, command, parameters, last_parameter).unwrap();
+        assert_eq!(Some("hello again"), modified.trailing());
+
+        let (prefix, command, parameters, last_parameter) = message.clone().into_parts();
+        assert_eq!(
+            message,
+            Message::from_parts(prefix, command, parameters, last_parameter).unwrap()
+        );
+    }
+
+    #[test]
+    fn new_from_str_literals() {
+        let message = Message::new(
+            None,
+            KnownCommand::Privmsg.into(),
+            ["#chan"],
+            Some("hello there"),
+        )
+        .unwrap();
+        assert_eq!(
+            b"PRIVMSG #chan :hello there\r\n",
+            &message.to_bytes().unwrap()[..]
+        );
+
+        // no middle parameters is the same as an empty list of them
+        let message =
+            Message::new(None, KnownCommand::Quit.into(), None::<&str>, None::<&str>).unwrap();
+        assert_eq!(&None, message.parameters());
+        assert_eq!(
+            message,
+            Message::new(None, KnownCommand::Quit.into(), [""; 0], None::<&str>).unwrap()
+        );
+    }
+
+    #[test]
+    fn builder_without_params() {
+        let built = Message::builder()
+            .command(Command::General("QUIT".to_string()))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            Message::new(
+                None,
+                Command::General("QUIT".to_string()),
+                None::<&str>,
+                None::<&str>
+            )
+            .unwrap(),
+            built
+        );
+    }
+
+    #[test]
+    fn empty_middle_parameter() {
+        let command = || Command::General("COMMAND".to_string());
+        assert!(matches!(
+            Message::new(None, command(), [""], None::<&str>),
+            Err(super::Error::CommandValidation)
+        ));
+        assert!(matches!(
+            Message::new(None, command(), ["foo", ""], None::<&str>),
+            Err(super::Error::CommandValidation)
+        ));
+
+        let message = Message::new(None, command(), ["foo"], Some("")).unwrap();
+        assert_eq!(b"COMMAND foo :\r\n", &message.to_bytes().unwrap()[..]);
+    }
+
+    #[test]
+    fn builder_validation() {
+        let builder = (1..=15).fold(
+            Message::builder().command(Command::General("COMMAND".to_string())),
+            |b, i| b.param(i.to_string()),
+        );
+        assert!(matches!(
+            builder.build(),
+            Err(super::Error::SimpleParameterValidation)
+        ));
+
+        assert!(matches!(
+            Message::builder().param("foo").build(),
+            Err(super::Error::MissingCommand)
+        ));
+    }
+
+    #[test]
+    fn builder_try_param() {
+        let builder = MessageBuilder::default().command(Command::General("MODE".to_string()));
+        let builder = ["#chan", "+k", "key"]
+            .into_iter()
+            .try_fold(builder, |b, p| b.try_param(p))
+            .unwrap();
+        assert_eq!(
+            b"MODE #chan +k key\r\n",
+            &builder.build().unwrap().to_bytes().unwrap()[..]
+        );
+
+        for bad in ["has space", "", ":colon", "cr\rlf"] {
+            assert!(matches!(
+                Message::builder().try_param(bad),
+                Err(super::Error::CommandValidation)
+            ));
+        }
+
+        let builder = (1..=14).fold(Message::builder(), |b, i| {
+            b.try_param(i.to_string()).unwrap()
+        });
+        assert!(matches!(
+            builder.try_param("15"),
+            Err(super::Error::SimpleParameterValidation)
+        ));
+
+        // the length is still only checked at the end
+        let builder = Message::builder()
+            .command(Command::General("COMMAND".to_string()))
+            .try_param("a".repeat(600))
+            .unwrap();
+        assert!(matches!(builder.build(), Err(super::Error::MessageTooLong)));
+    }
+
+    #[test]
+    fn split_privmsg_exact_boundary() {
+        // `PRIVMSG #chan :` and crlf
+        let available = 512 - 15 - 2;
+
+        let messages = Message::split_privmsg("#chan", &"a".repeat(available)).unwrap();
+        assert_eq!(1, messages.len());
+        assert_eq!(512, messages[0].to_bytes().unwrap().len());
+
+        let messages = Message::split_privmsg("#chan", &"a".repeat(available + 1)).unwrap();
+        assert_eq!(2, messages.len());
+        assert_eq!(&Some("a".to_string()), messages[1].last_parameter());
+    }
+
+    #[test]
+    fn split_privmsg_word_boundaries() {
+        let word = "word ".repeat(200);
+        let text = word.trim_end();
+
+        let messages = Message::split_privmsg("#chan", text).unwrap();
+        assert_eq!(3, messages.len());
+        for m in messages.iter() {
+            let trailing = m.last_parameter().as_ref().unwrap();
+            assert!(m.to_bytes().unwrap().len() <= 512);
+            assert!(!trailing.starts_with(' ') && !trailing.ends_with(' '));
+            assert!(trailing.split(' ').all(|w| w == "word"));
+        }
+        assert_eq!(
+            text,
+            messages
+                .iter()
+                .map(|m| m.last_parameter().as_deref().unwrap())
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+    }
+
+    #[test]
+    fn split_privmsg_oversized_word() {
+        let text = format!("short {}", "a".repeat(600));
+
+        let messages = Message::split_privmsg("#chan", &text).unwrap();
+        assert_eq!(3, messages.len());
+        assert_eq!(&Some("short".to_string()), messages[0].last_parameter());
+        assert_eq!(512, messages[1].to_bytes().unwrap().len());
+        assert_eq!(
+            600,
+            messages[1..]
+                .iter()
+                .map(|m| m.last_parameter().as_ref().unwrap().len())
+                .sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn split_privmsg_multibyte() {
+        // 3 bytes each, and 494 available bytes isn't a multiple of 3
+        let text = "あ".repeat(200);
+
+        let messages = Message::split_privmsg("#chan", &text).unwrap();
+        assert_eq!(2, messages.len());
+        assert_eq!(
+            text,
+            messages
+                .iter()
+                .map(|m| m.last_parameter().as_deref().unwrap())
+                .collect::<String>()
+        );
+        for m in messages.iter() {
+            assert!(m.to_bytes().unwrap().len() <= 512);
+        }
+    }
+
+    #[test]
+    fn join() {
+        let message =
+            Message::join(&[("#open", None), ("#a", Some("key1")), ("&b", Some("key2"))]).unwrap();
+        assert_eq!(
+            b"JOIN #a,&b,#open key1,key2\r\n",
+            &message.to_bytes().unwrap()[..]
+        );
+        assert_eq!(vec!["#a", "&b", "#open"], message.targets());
+        assert_eq!(vec!["key1", "key2"], message.keys());
+
+        let message = Message::join(&[("#a", None), ("#b", Some(""))]).unwrap();
+        assert_eq!(b"JOIN #a,#b\r\n", &message.to_bytes().unwrap()[..]);
+
+        assert!(matches!(
+            Message::join(&[("#has space", None)]),
+            Err(super::Error::ChannelValidation)
+        ));
+        assert!(matches!(
+            Message::join(&[("nochantype", None)]),
+            Err(super::Error::ChannelValidation)
+        ));
+        assert!(matches!(
+            Message::join(&[]),
+            Err(super::Error::ChannelValidation)
+        ));
+        assert!(matches!(
+            Message::join(&[("#a", Some("k,ey"))]),
+            Err(super::Error::CommandValidation)
+        ));
+    }
+
+    #[test]
+    fn register() {
+        let messages = Message::register("bot", "botuser", "The Bot", Some("hunter2")).unwrap();
+        let lines = messages
+            .iter()
+            .map(|m| String::from_utf8(m.to_bytes().unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "PASS :hunter2\r\n",
+                "NICK bot\r\n",
+                "USER botuser 0 * :The Bot\r\n"
+            ],
+            lines
+        );
+
+        let messages = Message::register("bot", "botuser", "Bot", None).unwrap();
+        assert_eq!(2, messages.len());
+        assert_eq!(Some(KnownCommand::Nick), messages[0].command().as_known());
+
+        assert!(matches!(
+            Message::register("9bot", "botuser", "Bot", None),
+            Err(super::Error::NickValidation)
+        ));
+        assert!(matches!(
+            Message::register("bot", "bot user", "Bot", None),
+            Err(super::Error::CommandValidation)
+        ));
+    }
+
+    #[test]
+    fn numeric_commands() {
+        for (raw, expected) in [
+            ("001 me :Welcome", Command::Numeric(1)),
+            ("433 * bot :In use", Command::Numeric(433)),
+            ("0001 me", Command::General("0001".to_string())),
+            ("01 me", Command::General("01".to_string())),
+            ("+01 me", Command::General("+01".to_string())),
+        ] {
+            let message: Message = raw.parse().unwrap();
+            assert_eq!(&expected, message.command());
         }
     }
-    impl PartialEq for UserMask {
-        fn eq(&self, other: &Self) -> bool {
-            self.nickname == other.nickname
-                && self.user == other.user
-                && self.server == other.server
+
+    #[test]
+    fn source_nick() {
+        let message: Message = ":nick!user@host PRIVMSG #chan :hi".parse().unwrap();
+        assert_eq!(Some("nick"), message.source_nick());
+
+        let message: Message = ":irc.server NOTICE * :hi".parse().unwrap();
+        assert_eq!(None, message.source_nick());
+        assert_eq!(None, message.prefix_ref().unwrap().nick());
+
+        let message: Message = "PING :abc".parse().unwrap();
+        assert_eq!(None, message.source_nick());
+    }
+
+    #[test]
+    fn targets() {
+        let single: Message = "PRIVMSG #a :hi".parse().unwrap();
+        assert_eq!(vec!["#a"], single.targets());
+        assert!(single.keys().is_empty());
+
+        let multiple: Message = "PRIVMSG #a,#b,nick :hi".parse().unwrap();
+        assert_eq!(vec!["#a", "#b", "nick"], multiple.targets());
+
+        let none: Message = "QUIT :bye".parse().unwrap();
+        assert!(none.targets().is_empty());
+    }
+
+    #[test]
+    fn join_keys() {
+        let join: Message = "JOIN #a,#b,#c key1,,key3".parse().unwrap();
+        assert_eq!(vec!["#a", "#b", "#c"], join.targets());
+        assert_eq!(vec!["key1", "", "key3"], join.keys());
+
+        let keyless: Message = "JOIN #a".parse().unwrap();
+        assert!(keyless.keys().is_empty());
+    }
+
+    #[test]
+    fn as_privmsg() {
+        let message: Message = ":nick!user@host PRIVMSG #chan :hello there"
+            .parse()
+            .unwrap();
+        assert_eq!(Some(("#chan", "hello there")), message.as_privmsg());
+        assert_eq!(None, message.as_notice());
+
+        let message: Message = ":nick!user@host PRIVMSG me hi".parse().unwrap();
+        assert_eq!(Some(("me", "hi")), message.as_privmsg());
+
+        let message: Message = ":irc.server NOTICE * :*** Looking up your hostname"
+            .parse()
+            .unwrap();
+        assert_eq!(None, message.as_privmsg());
+        assert_eq!(
+            Some(("*", "*** Looking up your hostname")),
+            message.as_notice()
+        );
+
+        // missing the text
+        let message: Message = "PRIVMSG #chan".parse().unwrap();
+        assert_eq!(None, message.as_privmsg());
+    }
+
+    #[test]
+    fn reply_target() {
+        let chantypes = crate::validate::DEFAULT_CHANTYPES;
+
+        let message: Message = ":nick!user@host PRIVMSG #chan :!help".parse().unwrap();
+        assert_eq!(Some("#chan"), message.reply_target("me", chantypes));
+
+        let message: Message = ":nick!user@host PRIVMSG Me :!help".parse().unwrap();
+        assert_eq!(Some("nick"), message.reply_target("me", chantypes));
+
+        // not for us, and a server can't be replied to
+        let message: Message = ":nick!user@host PRIVMSG other :!help".parse().unwrap();
+        assert_eq!(None, message.reply_target("me", chantypes));
+        let message: Message = ":irc.server PRIVMSG me :!help".parse().unwrap();
+        assert_eq!(None, message.reply_target("me", chantypes));
+    }
+
+    #[test]
+    fn topic() {
+        assert_eq!(
+            b"TOPIC #chan :new topic\r\n",
+            &Message::topic("#chan", "new topic")
+                .unwrap()
+                .to_bytes()
+                .unwrap()[..]
+        );
+        assert_eq!(
+            b"TOPIC #chan :\r\n",
+            &Message::topic("#chan", "").unwrap().to_bytes().unwrap()[..]
+        );
+        assert!(matches!(
+            Message::topic("nochannel", "topic"),
+            Err(super::Error::ChannelValidation)
+        ));
+    }
+
+    #[test]
+    fn is_sid() {
+        let Ok(Some((message, _))) = Message::parse(b":42A PING :irc.example.net\r\n") else {
+            panic!("Unable to parse message")
+        };
+        assert_eq!(&Some(Prefix::Server("42A".to_string())), message.prefix());
+        assert!(message.prefix().as_ref().unwrap().is_sid());
+
+        let Ok(Some((message, _))) = Message::parse(b":irc.example.net PING :42A\r\n") else {
+            panic!("Unable to parse message")
+        };
+        assert!(!message.prefix().as_ref().unwrap().is_sid());
+
+        assert!(!Prefix::Server("ABC".to_string()).is_sid());
+        assert!(!Prefix::Server("42AB".to_string()).is_sid());
+    }
+
+    #[test]
+    fn kick() {
+        assert_eq!(
+            b"KICK #chan troll :spamming\r\n",
+            &Message::kick("#chan", "troll", Some("spamming"))
+                .unwrap()
+                .to_bytes()
+                .unwrap()[..]
+        );
+        assert_eq!(
+            b"KICK #chan troll\r\n",
+            &Message::kick("#chan", "troll", None)
+                .unwrap()
+                .to_bytes()
+                .unwrap()[..]
+        );
+        assert!(matches!(
+            Message::kick("nochannel", "troll", None),
+            Err(super::Error::ChannelValidation)
+        ));
+        assert!(matches!(
+            Message::kick("#chan", "bad nick", None),
+            Err(super::Error::NickValidation)
+        ));
+    }
+
+    #[test]
+    fn part() {
+        assert_eq!(
+            b"PART #a,&b :see ya\r\n",
+            &Message::part(&["#a", "&b"], Some("see ya"))
+                .unwrap()
+                .to_bytes()
+                .unwrap()[..]
+        );
+        assert_eq!(
+            b"PART #a\r\n",
+            &Message::part(&["#a"], None).unwrap().to_bytes().unwrap()[..]
+        );
+        assert!(matches!(
+            Message::part(&[], None),
+            Err(super::Error::ChannelValidation)
+        ));
+        assert!(matches!(
+            Message::part(&["#a", "b"], None),
+            Err(super::Error::ChannelValidation)
+        ));
+    }
+
+    #[test]
+    fn pong_reply() {
+        let Ok(Some((ping, _))) = Message::parse(b"PING :abc\r\n") else {
+            panic!("Unable to parse message")
+        };
+        assert_eq!(
+            b"PONG :abc\r\n",
+            &ping.pong_reply().unwrap().to_bytes().unwrap()[..]
+        );
+
+        let Ok(Some((ping, _))) = Message::parse(b"PING abc\r\n") else {
+            panic!("Unable to parse message")
+        };
+        assert_eq!(
+            b"PONG abc\r\n",
+            &ping.pong_reply().unwrap().to_bytes().unwrap()[..]
+        );
+
+        let Ok(Some((privmsg, _))) = Message::parse(b"PRIVMSG #chan :abc\r\n") else {
+            panic!("Unable to parse message")
+        };
+        assert!(privmsg.pong_reply().is_none());
+    }
+
+    #[test]
+    fn user_mask_matches() {
+        let mask = UserMask {
+            nickname: "Nick[away]".to_string(),
+            user: Some("~user".to_string()),
+            server: Some("host.Example.com".to_string()),
+        };
+
+        assert!(mask.matches("*!*@host.example.com"));
+        assert!(mask.matches("*!*@*.example.com"));
+        assert!(mask.matches("nick{AWAY}!*@*"));
+        assert!(mask.matches("nick?away?!?user@*"));
+        assert!(mask.matches("*@*.com"));
+        assert!(mask.matches("nick*"));
+        assert!(mask.matches("*!*@*"));
+
+        assert!(!mask.matches("*!*@other.example.com"));
+        assert!(!mask.matches("other!*@*"));
+        assert!(!mask.matches("*!user@*"));
+        assert!(!mask.matches("nick?!*@*"));
+        assert!(!mask.matches("*!*@*.example.net"));
+    }
+
+    #[test]
+    fn command_eq_ignore_case() {
+        let privmsg = Command::General("PrivMsg".to_string());
+        assert!(privmsg.eq_ignore_case("privmsg"));
+        assert!(privmsg.eq_ignore_case("PRIVMSG"));
+        assert!(!privmsg.eq_ignore_case("notice"));
+
+        let welcome = Command::Numeric(1);
+        assert!(welcome.eq_ignore_case("001"));
+        assert!(welcome.eq_ignore_case("1"));
+        assert!(!welcome.eq_ignore_case("002"));
+        assert!(!welcome.eq_ignore_case("+1"));
+        assert!(!welcome.eq_ignore_case("RPL_WELCOME"));
+        assert!(!welcome.eq_ignore_case(""));
+
+        assert!(!Command::General("001".to_string()).eq_ignore_case("1"));
+    }
+
+    #[test]
+    fn known_command_case_insensitive() {
+        assert_eq!(
+            Some(KnownCommand::Privmsg),
+            Command::General("privmsg".to_string()).as_known()
+        );
+        assert_eq!(
+            Some(KnownCommand::Privmsg),
+            Command::General("PRIVMSG".to_string()).as_known()
+        );
+        assert_eq!(None, Command::General("FOO".to_string()).as_known());
+        assert_eq!(None, Command::Numeric(1).as_known());
+    }
+
+    #[test]
+    fn known_command_round_trip() {
+        for k in KnownCommand::ALL {
+            assert_eq!(Some(k), Command::from(k).as_known());
         }
     }
+
+    #[test]
+    fn hash_matches_eq() {
+        let parse = || -> Message {
+            "@id=1 :nick!user@host PRIVMSG #chan :hello"
+                .parse()
+                .unwrap()
+        };
+
+        let set: std::collections::HashSet<Message> = [parse(), parse()].into_iter().collect();
+        assert_eq!(1, set.len());
+    }
 }
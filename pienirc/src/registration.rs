@@ -0,0 +1,136 @@
+use std::{collections::HashSet, io};
+
+use crate::{
+    cap::CapNegotiator, nick::NickFallback, numerics, Command, KnownCommand, Message, Transport,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum RegistrationError {
+    #[error("I/O error.")]
+    Io(#[from] io::Error),
+
+    #[error("Protocol error.")]
+    Protocol(#[from] crate::Error),
+
+    #[error("Connection closed before registration completed.")]
+    Closed,
+
+    #[error("The server rejected the password.")]
+    PasswordMismatch,
+
+    #[error("Every nick was rejected.")]
+    NicksExhausted,
+
+    #[error("The server sent an error: `{0}`.")]
+    Server(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct RegistrationConfig {
+    pub nick: String,
+    pub username: String,
+    pub realname: String,
+    pub password: Option<String>,
+    // if empty, CAP negotiation is skipped entirely
+    pub capabilities: Vec<String>,
+    // what to try if the nick is taken or rejected
+    pub fallback: NickFallback,
+}
+
+impl RegistrationConfig {
+    // falls back to `nick_`, `nick__`, and `nick___`
+    pub fn new(nick: &str, username: &str, realname: &str) -> Self {
+        RegistrationConfig {
+            nick: nick.to_string(),
+            username: username.to_string(),
+            realname: realname.to_string(),
+            password: None,
+            capabilities: Vec::new(),
+            fallback: NickFallback::suffixed(nick, 3),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RegistrationOutcome {
+    // what the server ended up calling us, which may be a fallback
+    pub nick: String,
+    pub capabilities: HashSet<String>,
+    pub welcome: Message,
+}
+
+// drives the whole handshake, answering PINGs along the way, until RPL_WELCOME arrives
+pub async fn register<T: Transport>(
+    transport: &mut T,
+    mut config: RegistrationConfig,
+) -> Result<RegistrationOutcome, RegistrationError>
+where
+    RegistrationError: From<T::Error>,
+{
+    let mut negotiator = CapNegotiator::new(config.capabilities.iter().cloned());
+    if !config.capabilities.is_empty() {
+        transport.send(negotiator.start()?).await?;
+    }
+    for message in Message::register(
+        &config.nick,
+        &config.username,
+        &config.realname,
+        config.password.as_deref(),
+    )? {
+        transport.send(message).await?;
+    }
+    transport.flush().await?;
+
+    loop {
+        let message = match transport.receive().await.map_err(RegistrationError::from) {
+            Ok(Some(message)) => message,
+            Ok(None) => return Err(RegistrationError::Closed),
+            // only the one line was bad, and it's already been dropped
+            Err(RegistrationError::Protocol(_)) => continue,
+            Err(err) => return Err(err),
+        };
+
+        let replies = match (message.command(), message.command().as_known()) {
+            (_, Some(KnownCommand::Ping)) => message.pong_reply().into_iter().collect(),
+            (_, Some(KnownCommand::Cap)) => negotiator.handle(&message)?,
+            (_, Some(KnownCommand::Error)) => {
+                return Err(RegistrationError::Server(
+                    message.trailing().unwrap_or_default().to_string(),
+                ))
+            }
+            (&Command::Numeric(numerics::RPL_WELCOME), _) => {
+                return Ok(RegistrationOutcome {
+                    nick: message.params().first().cloned().unwrap_or(config.nick),
+                    capabilities: negotiator.acknowledged().clone(),
+                    welcome: message,
+                })
+            }
+            (&Command::Numeric(numerics::ERR_PASSWDMISMATCH), _) => {
+                return Err(RegistrationError::PasswordMismatch)
+            }
+            (
+                &Command::Numeric(numerics::ERR_NICKNAMEINUSE | numerics::ERR_ERRONEUSNICKNAME),
+                _,
+            ) => {
+                let nick = config
+                    .fallback
+                    .next_nick()
+                    .ok_or(RegistrationError::NicksExhausted)?;
+                vec![Message::new(
+                    None,
+                    KnownCommand::Nick.into(),
+                    [nick],
+                    None::<&str>,
+                )?]
+            }
+            _ => Vec::new(),
+        };
+
+        if !replies.is_empty() {
+            for reply in replies {
+                transport.send(reply).await?;
+            }
+            transport.flush().await?;
+        }
+    }
+}
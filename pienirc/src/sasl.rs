@@ -0,0 +1,122 @@
+use crate::{KnownCommand, Message, Result};
+
+// payloads longer than this are split across multiple AUTHENTICATE messages
+const CHUNK_LEN: usize = 400;
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// the base64 of `authzid\0authcid\0password`, where an empty authzid means the same as authcid
+pub fn plain_payload(authcid: &str, authzid: Option<&str>, password: &str) -> String {
+    let raw = [authzid.unwrap_or(""), authcid, password].join("\0");
+    base64(raw.as_bytes())
+}
+
+// `AUTHENTICATE PLAIN`, after which the server should reply with `AUTHENTICATE +`
+pub fn authenticate_plain() -> Result<Message> {
+    authenticate_message("PLAIN")
+}
+
+// the payload, split into 400 byte chunks.
+// if the last chunk is exactly 400 bytes (or there is no payload), a `+` follows so the server knows it's done.
+pub fn authenticate(payload: &str) -> Result<Vec<Message>> {
+    // base64 is all ascii, so chunking bytes won't split a codepoint
+    let mut messages = payload
+        .as_bytes()
+        .chunks(CHUNK_LEN)
+        .map(|c| authenticate_message(&String::from_utf8_lossy(c)))
+        .collect::<Result<Vec<_>>>()?;
+
+    if payload.len() % CHUNK_LEN == 0 {
+        messages.push(authenticate_message("+")?);
+    }
+
+    Ok(messages)
+}
+
+fn authenticate_message(parameter: &str) -> Result<Message> {
+    Message::new(
+        None,
+        KnownCommand::Authenticate.into(),
+        [parameter],
+        None::<&str>,
+    )
+}
+
+fn base64(input: &[u8]) -> String {
+    let mut s = String::with_capacity(input.len().div_ceil(3) * 4);
+
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, b[0], b[1], b[2]]);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_padding() {
+        assert_eq!("", base64(b""));
+        assert_eq!("Zg==", base64(b"f"));
+        assert_eq!("Zm8=", base64(b"fo"));
+        assert_eq!("Zm9v", base64(b"foo"));
+        assert_eq!("Zm9vYg==", base64(b"foob"));
+    }
+
+    #[test]
+    fn plain_payload_known_credentials() {
+        assert_eq!("AGplbGx5AGZpc2g=", plain_payload("jelly", None, "fish"));
+        assert_eq!(
+            "YWRtaW4AamVsbHkAZmlzaA==",
+            plain_payload("jelly", Some("admin"), "fish")
+        );
+    }
+
+    #[test]
+    fn authenticate_messages() {
+        assert_eq!(
+            "AUTHENTICATE PLAIN",
+            authenticate_plain().unwrap().to_string()
+        );
+
+        let messages = authenticate("AGplbGx5AGZpc2g=").unwrap();
+        assert_eq!(
+            vec!["AUTHENTICATE AGplbGx5AGZpc2g="],
+            messages.iter().map(|m| m.to_string()).collect::<Vec<_>>()
+        );
+
+        let messages = authenticate("").unwrap();
+        assert_eq!(
+            vec!["AUTHENTICATE +"],
+            messages.iter().map(|m| m.to_string()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn authenticate_chunking_boundary() {
+        let exact = "A".repeat(800);
+        let messages = authenticate(&exact).unwrap();
+        assert_eq!(3, messages.len());
+        assert_eq!(&Some(vec!["A".repeat(400)]), messages[1].parameters());
+        assert_eq!(&Some(vec!["+".to_string()]), messages[2].parameters());
+
+        let over = "A".repeat(801);
+        let messages = authenticate(&over).unwrap();
+        assert_eq!(3, messages.len());
+        assert_eq!(&Some(vec!["A".to_string()]), messages[2].parameters());
+    }
+}
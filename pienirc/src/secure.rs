@@ -0,0 +1,40 @@
+// for checking secrets like passwords, where `==` returning at the first mismatch leaks how much matched
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    // the length of the longer input still shows, but bailing on a length mismatch would also leak the
+    // secret's length, so every byte gets compared regardless, with the shorter one padded out
+    let mut diff = a.len() ^ b.len();
+    for i in 0..a.len().max(b.len()) {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        diff |= usize::from(x ^ y);
+    }
+
+    // keeps the optimizer from turning the loop back into an early return
+    core::hint::black_box(diff) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal() {
+        assert!(ct_eq(b"hunter2", b"hunter2"));
+        assert!(ct_eq(b"", b""));
+    }
+
+    #[test]
+    fn unequal() {
+        assert!(!ct_eq(b"hunter2", b"hunter3"));
+        assert!(!ct_eq(b"hunter2", b"Hunter2"));
+    }
+
+    #[test]
+    fn different_lengths() {
+        assert!(!ct_eq(b"hunter", b"hunter2"));
+        assert!(!ct_eq(b"hunter2", b"hunter"));
+        assert!(!ct_eq(b"", b"a"));
+        // padding with zeros mustn't make these look the same
+        assert!(!ct_eq(b"a", b"a\0"));
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{Command, Message};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Fail,
+    Warn,
+    Note,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StandardReply {
+    pub severity: Severity,
+    // the command the reply is about, or `*` if it isn't about any one command
+    pub command_context: String,
+    pub code: String,
+    pub context: Vec<String>,
+    pub description: String,
+}
+
+// FAIL, WARN, and NOTE, like `FAIL JOIN NEED_REGISTRATION #chan :You must register`
+pub fn parse(msg: &Message) -> Option<StandardReply> {
+    let Command::General(command) = msg.command() else {
+        return None;
+    };
+    let severity = match command.to_ascii_uppercase().as_str() {
+        "FAIL" => Severity::Fail,
+        "WARN" => Severity::Warn,
+        "NOTE" => Severity::Note,
+        _ => return None,
+    };
+
+    // the description is almost always the trailing parameter, but doesn't strictly have to be
+    let mut parameters = msg.all_params().collect::<Vec<_>>();
+    if parameters.len() < 3 {
+        return None;
+    }
+    let description = parameters.pop()?.to_string();
+
+    Some(StandardReply {
+        severity,
+        command_context: parameters[0].to_string(),
+        code: parameters[1].to_string(),
+        context: parameters[2..].iter().map(|p| p.to_string()).collect(),
+        description,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fail_with_context() {
+        let message: Message = "FAIL JOIN NEED_REGISTRATION #chan :You must register"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            Some(StandardReply {
+                severity: Severity::Fail,
+                command_context: "JOIN".to_string(),
+                code: "NEED_REGISTRATION".to_string(),
+                context: vec!["#chan".to_string()],
+                description: "You must register".to_string(),
+            }),
+            parse(&message)
+        );
+    }
+
+    #[test]
+    fn warn_and_note() {
+        let message: Message = ":irc.server warn REHASH CERTS_EXPIRED :Certificate expired"
+            .parse()
+            .unwrap();
+        let reply = parse(&message).unwrap();
+        assert_eq!(Severity::Warn, reply.severity);
+        assert!(reply.context.is_empty());
+
+        let message: Message = "NOTE * OPER_MESSAGE :Server is restarting".parse().unwrap();
+        let reply = parse(&message).unwrap();
+        assert_eq!(Severity::Note, reply.severity);
+        assert_eq!("*", reply.command_context);
+        assert_eq!("Server is restarting", reply.description);
+    }
+
+    #[test]
+    fn not_standard_reply() {
+        assert_eq!(None, parse(&"PRIVMSG #chan :hi".parse().unwrap()));
+        assert_eq!(None, parse(&"FAIL JOIN".parse().unwrap()));
+    }
+}
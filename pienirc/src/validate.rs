@@ -0,0 +1,90 @@
+const MAX_CHANNEL_LEN: usize = 50;
+// the rfc limit, though most servers advertise a higher NICKLEN
+const MAX_NICK_LEN: usize = 9;
+// for when the server hasn't sent CHANTYPES, and for building messages, which don't know what the server sent.
+// every prefix the rfcs define, since none of them can start a nick anyway.
+pub const DEFAULT_CHANTYPES: &str = "#&+!";
+
+// chantypes is the value of the CHANTYPES ISUPPORT token, like `#&`
+pub fn is_valid_channel(name: &str, chantypes: &str) -> bool {
+    let mut chars = name.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+
+    // bell is disallowed by the rfc along with the others, which all control chars cover
+    name.len() <= MAX_CHANNEL_LEN
+        && chantypes.contains(first)
+        && chars.as_str().chars().next().is_some()
+        && !chars.any(|c| c == ' ' || c == ',' || c == ':' || c.is_control())
+}
+
+// unlike is_valid_channel, only looks at the prefix, for deciding where a message was sent
+pub fn is_channel_target(target: &str, chantypes: &str) -> bool {
+    target.chars().next().is_some_and(|c| chantypes.contains(c))
+}
+
+pub fn is_valid_nick(nick: &str) -> bool {
+    is_valid_nick_len(nick, MAX_NICK_LEN)
+}
+
+// for when the server advertises its own NICKLEN
+pub fn is_valid_nick_len(nick: &str, max_len: usize) -> bool {
+    // `[]\`_^{|}`
+    let special = |c: char| ('\x5b'..='\x60').contains(&c) || ('\x7b'..='\x7d').contains(&c);
+
+    let mut chars = nick.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+
+    nick.len() <= max_len
+        && (first.is_ascii_alphabetic() || special(first))
+        && chars.all(|c| c.is_ascii_alphanumeric() || special(c) || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channels() {
+        assert!(is_valid_channel("#valid", "#&"));
+        assert!(is_valid_channel("&valid", "#&"));
+        assert!(is_valid_channel("##double", "#"));
+
+        assert!(!is_valid_channel("#has space", "#&"));
+        assert!(!is_valid_channel("#has,comma", "#&"));
+        assert!(!is_valid_channel("#bell\x07", "#&"));
+        assert!(!is_valid_channel("&valid", "#"));
+        assert!(!is_valid_channel("nochantype", "#&"));
+        assert!(!is_valid_channel("#", "#"));
+        assert!(!is_valid_channel("", "#"));
+        assert!(!is_valid_channel(&format!("#{}", "a".repeat(50)), "#"));
+    }
+
+    #[test]
+    fn channel_targets() {
+        assert!(is_channel_target("#chan", DEFAULT_CHANTYPES));
+        assert!(is_channel_target("&local", DEFAULT_CHANTYPES));
+        assert!(!is_channel_target("nick", DEFAULT_CHANTYPES));
+        assert!(is_channel_target("+chan", DEFAULT_CHANTYPES));
+        assert!(!is_channel_target("+chan", "#&"));
+        assert!(!is_channel_target("", DEFAULT_CHANTYPES));
+    }
+
+    #[test]
+    fn nicks() {
+        assert!(is_valid_nick("nick"));
+        assert!(is_valid_nick("[away]`_^"));
+        assert!(is_valid_nick("{n|ck}-9"));
+
+        assert!(!is_valid_nick("toolongnick"));
+        assert!(is_valid_nick_len("toolongnick", 30));
+        assert!(!is_valid_nick("9lives"));
+        assert!(!is_valid_nick("-dash"));
+        assert!(!is_valid_nick("has space"));
+        assert!(!is_valid_nick("nick!"));
+        assert!(!is_valid_nick(""));
+    }
+}
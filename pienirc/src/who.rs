@@ -0,0 +1,175 @@
+use crate::{numerics, Command, Message};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhoEntry {
+    // `*` if the reply isn't for a particular channel
+    pub channel: String,
+    pub user: String,
+    pub host: String,
+    pub server: String,
+    pub nick: String,
+    // like `H@`, for here and opped, or `G` for gone
+    pub flags: String,
+    pub hopcount: u32,
+    pub realname: String,
+}
+
+// only the fields that were asked for are present
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WhoxEntry {
+    pub token: Option<String>,
+    pub channel: Option<String>,
+    pub user: Option<String>,
+    pub ip: Option<String>,
+    pub host: Option<String>,
+    pub server: Option<String>,
+    pub nick: Option<String>,
+    pub flags: Option<String>,
+    pub hopcount: Option<u32>,
+    pub idle: Option<u64>,
+    // `0` if not logged in
+    pub account: Option<String>,
+    pub oplevel: Option<String>,
+    pub realname: Option<String>,
+}
+
+// RPL_WHOREPLY, like `:server 352 me #chan ~user host irc.server nick H@ :0 Real Name`
+pub fn parse_352(msg: &Message) -> Option<WhoEntry> {
+    if msg.command() != &Command::Numeric(numerics::RPL_WHOREPLY) {
+        return None;
+    }
+
+    // the first parameter is our nick
+    let [_, channel, user, host, server, nick, flags, last] =
+        msg.all_params().collect::<Vec<_>>()[..]
+    else {
+        return None;
+    };
+    // the hopcount shares the last parameter with the realname
+    let (hopcount, realname) = last.split_once(' ').unwrap_or((last, ""));
+
+    Some(WhoEntry {
+        channel: channel.to_string(),
+        user: user.to_string(),
+        host: host.to_string(),
+        server: server.to_string(),
+        nick: nick.to_string(),
+        flags: flags.to_string(),
+        hopcount: hopcount.parse().ok()?,
+        realname: realname.to_string(),
+    })
+}
+
+// RPL_WHOSPCRPL, where fields are what was sent with the WHO, like `%tcuhnar,42` or just `cuhnar`.
+// the reply always has the fields in the same order, regardless of the order they were asked for in.
+pub fn parse_354(msg: &Message, fields: &str) -> Option<WhoxEntry> {
+    if msg.command() != &Command::Numeric(numerics::RPL_WHOSPCRPL) {
+        return None;
+    }
+
+    let fields = fields.strip_prefix('%').unwrap_or(fields);
+    let fields = fields.split_once(',').map_or(fields, |(f, _)| f);
+
+    // the first parameter is our nick, and the realname, if asked for, is the trailing parameter
+    let mut values = msg.all_params().skip(1);
+    let mut entry = WhoxEntry::default();
+
+    for field in "tcuihsnfdlaor".chars().filter(|f| fields.contains(*f)) {
+        let value = values.next()?;
+        let string = || Some(value.to_string());
+        match field {
+            't' => entry.token = string(),
+            'c' => entry.channel = string(),
+            'u' => entry.user = string(),
+            'i' => entry.ip = string(),
+            'h' => entry.host = string(),
+            's' => entry.server = string(),
+            'n' => entry.nick = string(),
+            'f' => entry.flags = string(),
+            'd' => entry.hopcount = Some(value.parse().ok()?),
+            'l' => entry.idle = Some(value.parse().ok()?),
+            'a' => entry.account = string(),
+            'o' => entry.oplevel = string(),
+            _ => entry.realname = string(),
+        }
+    }
+
+    Some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn who_reply() {
+        let message: Message =
+            ":tantalum.libera.chat 352 me #libera ~someone user/someone/x-1234 zirconium.libera.chat someone H@ :0 Some One"
+                .parse()
+                .unwrap();
+
+        assert_eq!(
+            Some(WhoEntry {
+                channel: "#libera".to_string(),
+                user: "~someone".to_string(),
+                host: "user/someone/x-1234".to_string(),
+                server: "zirconium.libera.chat".to_string(),
+                nick: "someone".to_string(),
+                flags: "H@".to_string(),
+                hopcount: 0,
+                realname: "Some One".to_string(),
+            }),
+            parse_352(&message)
+        );
+    }
+
+    #[test]
+    fn who_reply_empty_realname() {
+        let message: Message = ":irc.server 352 me * user host irc.server nick G :3"
+            .parse()
+            .unwrap();
+
+        let entry = parse_352(&message).unwrap();
+        assert_eq!(3, entry.hopcount);
+        assert_eq!("", entry.realname);
+
+        // the same, but without a colon
+        let message: Message = ":irc.server 352 me * user host irc.server nick G 3"
+            .parse()
+            .unwrap();
+        assert_eq!(Some(entry), parse_352(&message));
+    }
+
+    #[test]
+    fn whox_reply() {
+        let message: Message =
+            ":tantalum.libera.chat 354 me 42 #libera ~someone 192.0.2.1 user/someone/x-1234 someone H@ someone :Some One"
+                .parse()
+                .unwrap();
+
+        let some = |s: &str| Some(s.to_string());
+        assert_eq!(
+            Some(WhoxEntry {
+                token: some("42"),
+                channel: some("#libera"),
+                user: some("~someone"),
+                ip: some("192.0.2.1"),
+                host: some("user/someone/x-1234"),
+                nick: some("someone"),
+                flags: some("H@"),
+                account: some("someone"),
+                realname: some("Some One"),
+                ..Default::default()
+            }),
+            // asked for out of order, but the reply comes in the standard order
+            parse_354(&message, "%rahcuntfi,42")
+        );
+    }
+
+    #[test]
+    fn whox_reply_missing_fields() {
+        let message: Message = ":irc.server 354 me #chan nick".parse().unwrap();
+        assert_eq!(None, parse_354(&message, "cna"));
+        assert_eq!(None, parse_352(&message));
+    }
+}
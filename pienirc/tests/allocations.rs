@@ -0,0 +1,51 @@
+// its own test binary, so that the counting allocator doesn't get installed for every other test
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use pienirc::{Command, Message};
+
+// counts allocations per thread, since tests run concurrently
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|a| a.set(a.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.with(|a| a.get());
+    let result = f();
+    (result, ALLOCATIONS.with(|a| a.get()) - before)
+}
+
+#[test]
+fn numeric_command_without_allocating() {
+    // the same line otherwise, so the only difference should be the command string
+    let (numeric, numeric_count) = allocations(|| Message::parse(b":server 353 nick\r\n"));
+    let (general, general_count) = allocations(|| Message::parse(b":server ABC nick\r\n"));
+
+    assert_eq!(
+        &Command::Numeric(353),
+        numeric.unwrap().unwrap().0.command()
+    );
+    assert_eq!(
+        &Command::General("ABC".to_string()),
+        general.unwrap().unwrap().0.command()
+    );
+    assert!(numeric_count < general_count);
+}